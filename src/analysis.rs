@@ -0,0 +1,294 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::backend::SolveError;
+use crate::json::Recipe;
+use crate::solver::{get_limit_constraint_name, get_target_constraint_name, Factory, NodeType, Solver, Target};
+
+const PERTURBATION: f64 = 1.0;
+const BINDING_TOLERANCE: f64 = 0.0001;
+
+#[derive(Debug, Clone)]
+pub struct PlanMetrics {
+    pub objective: f64,
+    pub resources: f64,
+    pub buildings: f64,
+    pub power: f64,
+}
+
+impl PlanMetrics {
+    fn new(factory: &Factory) -> PlanMetrics {
+        let mut resources: f64 = 0.0;
+        let mut buildings: f64 = 0.0;
+        for node in factory.nodes.values() {
+            if matches!(node.node_type, NodeType::RawResource | NodeType::Extractor) {
+                resources += node.outputs.values().fold(0.0, |acc, item_rate| acc + item_rate.rate);
+            }
+            buildings += node.buildings;
+        }
+        return PlanMetrics {
+            objective: factory.objective,
+            resources,
+            buildings,
+            power: factory.power_consumption,
+        };
+    }
+
+    fn infeasible() -> PlanMetrics {
+        return PlanMetrics {
+            objective: f64::INFINITY,
+            resources: f64::INFINITY,
+            buildings: f64::INFINITY,
+            power: f64::INFINITY,
+        };
+    }
+
+    fn improvement_over(&self, other: &PlanMetrics) -> PlanMetrics {
+        return PlanMetrics {
+            objective: other.objective - self.objective,
+            resources: other.resources - self.resources,
+            buildings: other.buildings - self.buildings,
+            power: other.power - self.power,
+        };
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AlternateValue {
+    pub recipe_id: String,
+    pub name: String,
+    pub improvement: PlanMetrics,
+}
+
+#[derive(Debug, Clone)]
+pub struct AlternateRanking {
+    pub baseline: PlanMetrics,
+    pub candidates: Vec<AlternateValue>,
+    pub preserved: Vec<AlternateValue>,
+}
+
+fn solve_metrics(solver: &mut Solver) -> PlanMetrics {
+    return match solver.try_solve() {
+        Ok(factory) => PlanMetrics::new(&factory),
+        Err(_) => PlanMetrics::infeasible(),
+    };
+}
+
+fn sort_by_benefit(values: &mut [AlternateValue]) -> () {
+    values.sort_by(|a, b| b.improvement.objective.partial_cmp(&a.improvement.objective).unwrap_or(Ordering::Equal));
+}
+
+//Candidates show the gain from enabling each missing alternate on its own,
+//preserved alternates show the loss from removing each enabled one.
+//Each change is undone after its solve, so the solver is left as it was passed in.
+//There is nothing to compare against if the baseline itself is infeasible
+pub fn rank_alternates(solver: &mut Solver) -> Result<AlternateRanking, SolveError> {
+    let baseline: PlanMetrics = PlanMetrics::new(&solver.try_solve()?);
+    let alternates: Vec<Recipe> = solver.get_data().recipes.values().filter(|&recipe| recipe.alternate).cloned().collect::<Vec<Recipe>>();
+    let mut candidates: Vec<AlternateValue> = Vec::new();
+    let mut preserved: Vec<AlternateValue> = Vec::new();
+    for recipe in alternates {
+        if solver.is_recipe_allowed(&recipe.class_name) {
+            solver.remove_recipe(&recipe.class_name);
+            let metrics: PlanMetrics = solve_metrics(solver);
+            solver.allow_recipe(&recipe.class_name);
+            preserved.push(AlternateValue { recipe_id: recipe.class_name.clone(), name: recipe.name.clone(), improvement: baseline.improvement_over(&metrics) });
+        } else {
+            solver.allow_recipe(&recipe.class_name);
+            let metrics: PlanMetrics = solve_metrics(solver);
+            solver.remove_recipe(&recipe.class_name);
+            candidates.push(AlternateValue { recipe_id: recipe.class_name.clone(), name: recipe.name.clone(), improvement: metrics.improvement_over(&baseline) });
+        }
+    }
+    sort_by_benefit(&mut candidates);
+    sort_by_benefit(&mut preserved);
+    return Ok(AlternateRanking { baseline, candidates, preserved });
+}
+
+#[derive(Debug, Clone)]
+pub struct ShadowPrice {
+    pub item_id: String,
+    pub name: String,
+    pub rate: f64,
+    pub bound: f64,
+    pub binding: bool,
+    pub price: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SensitivityReport {
+    pub objective: f64,
+    pub resources: Vec<ShadowPrice>,
+    pub targets: Vec<ShadowPrice>,
+}
+
+fn get_item_flow(factory: &Factory, item_id: &str, incoming: bool) -> f64 {
+    return match factory.nodes.get(item_id) {
+        Some(node) if incoming => node.inputs.values().fold(0.0, |acc, item_rate| acc + item_rate.rate),
+        Some(node) => node.outputs.values().fold(0.0, |acc, item_rate| acc + item_rate.rate),
+        None => 0.0,
+    };
+}
+
+fn get_perturbed_objective(solver: &mut Solver, objective: f64) -> f64 {
+    return match solver.try_solve() {
+        Ok(factory) => (factory.objective - objective) / PERTURBATION,
+        Err(_) => f64::INFINITY,
+    };
+}
+
+//Prices are the backend's duals when it has them. Otherwise each binding bound is moved by PERTURBATION
+//and the model re-solved; the resulting change in objective per unit matches the dual within the current basis.
+//Resource weights are fixed when the config is loaded, so moving a limit doesn't re-weight anything.
+//There are no prices to report if the plan itself is infeasible
+pub fn shadow_prices(solver: &mut Solver) -> Result<SensitivityReport, SolveError> {
+    let factory: Factory = solver.try_solve()?;
+    let mut resources: Vec<ShadowPrice> = Vec::new();
+    for item_id in solver.get_resources().item_ids() {
+        let limit: f64 = solver.get_resources().get_limit(&item_id);
+        let rate: f64 = get_item_flow(&factory, &item_id, false);
+        let binding: bool = limit.is_finite() && rate >= limit - BINDING_TOLERANCE;
+        let mut price: f64 = 0.0;
+        if binding {
+            price = match factory.duals.get(&get_limit_constraint_name(&item_id)) {
+                Some(&dual) => dual,
+                None => {
+                    solver.set_resource_limit(&item_id, limit + PERTURBATION);
+                    let price: f64 = get_perturbed_objective(solver, factory.objective);
+                    solver.set_resource_limit(&item_id, limit);
+                    price
+                }
+            };
+        }
+        let name: String = solver.get_data().items.get(&item_id).map_or(item_id.clone(), |item| item.name.clone());
+        resources.push(ShadowPrice { item_id, name, rate, bound: limit, binding, price });
+    }
+    let mut targets: Vec<ShadowPrice> = Vec::new();
+    for (item_id, target) in solver.get_targets().clone() {
+        let rate: f64 = get_item_flow(&factory, &item_id, true);
+        let is_at = |bound: f64| (rate - bound).abs() <= BINDING_TOLERANCE;
+        let constraint_name: String = get_target_constraint_name(&item_id);
+        let perturbed: Option<(f64, Target, String)> = match target {
+            Target::Exact(bound) => Some((bound, Target::Exact(bound + PERTURBATION), constraint_name)),
+            Target::AtLeast(bound) if is_at(bound) => Some((bound, Target::AtLeast(bound + PERTURBATION), constraint_name)),
+            Target::AtMost(bound) if is_at(bound) => Some((bound, Target::AtMost(bound + PERTURBATION), constraint_name)),
+            Target::Range(min_bound, max_bound) if is_at(min_bound) => Some((min_bound, Target::Range(min_bound + PERTURBATION, max_bound.max(min_bound + PERTURBATION)), format!("{}_min", constraint_name))),
+            Target::Range(min_bound, max_bound) if is_at(max_bound) => Some((max_bound, Target::Range(min_bound, max_bound + PERTURBATION), format!("{}_max", constraint_name))),
+            _ => None,
+        };
+        let name: String = solver.get_data().get_item_name(&item_id);
+        match perturbed {
+            Some((bound, perturbed_target, constraint_name)) => {
+                let price: f64 = match factory.duals.get(&constraint_name) {
+                    Some(&dual) => dual,
+                    None => {
+                        solver.set_target(&item_id, perturbed_target);
+                        let price: f64 = get_perturbed_objective(solver, factory.objective);
+                        solver.set_target(&item_id, target);
+                        price
+                    }
+                };
+                targets.push(ShadowPrice { item_id, name, rate, bound, binding: true, price });
+            }
+            None => targets.push(ShadowPrice { item_id, name, rate, bound: rate, binding: false, price: 0.0 }),
+        }
+    }
+    return Ok(SensitivityReport { objective: factory.objective, resources, targets });
+}
+
+#[derive(Debug, Clone)]
+pub enum SweepParameter {
+    Target(String),
+    ResourceLimit(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct SweepPoint {
+    pub value: f64,
+    pub feasible: bool,
+    pub objective: f64,
+    pub resources: HashMap<String, f64>,
+    pub recipes: Vec<String>,
+    pub mix_changed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Sweep {
+    pub parameter: SweepParameter,
+    pub points: Vec<SweepPoint>,
+}
+
+impl Sweep {
+    pub fn get_mix_changes(&self) -> Vec<&SweepPoint> {
+        return self.points.iter().filter(|&point| point.mix_changed).collect::<Vec<&SweepPoint>>();
+    }
+}
+
+//Keeps the kind of an existing target so sweeping an at-least target stays an at-least target
+fn get_swept_target(target: Option<Target>, value: f64) -> Target {
+    return match target {
+        Some(Target::AtLeast(_)) => Target::AtLeast(value),
+        Some(Target::AtMost(_)) => Target::AtMost(value),
+        Some(Target::Range(_, max_rate)) => Target::Range(value, max_rate.max(value)),
+        _ => Target::Exact(value),
+    };
+}
+
+fn get_sweep_point(solver: &mut Solver, value: f64) -> SweepPoint {
+    let factory: Factory = match solver.try_solve() {
+        Ok(factory) => factory,
+        Err(_) => return SweepPoint { value, feasible: false, objective: f64::INFINITY, resources: HashMap::new(), recipes: Vec::new(), mix_changed: false },
+    };
+    let mut resources: HashMap<String, f64> = HashMap::new();
+    let mut recipes: Vec<String> = Vec::new();
+    for (node_id, node) in factory.nodes.iter() {
+        if matches!(node.node_type, NodeType::RawResource | NodeType::Extractor) {
+            resources.insert(node_id.clone(), node.outputs.values().fold(0.0, |acc, item_rate| acc + item_rate.rate));
+        } else if solver.get_data().recipes.contains_key(node_id) {
+            recipes.push(node_id.clone());
+        }
+    }
+    recipes.sort();
+    return SweepPoint { value, feasible: true, objective: factory.objective, resources, recipes, mix_changed: false };
+}
+
+//Re-solves the same solver at each of steps + 1 evenly spaced values from start to end,
+//marking every point whose set of recipes differs from the previous point; the swept value is restored afterwards
+//Resource weights don't depend on limits, so objectives are comparable across a limit sweep
+pub fn sweep(solver: &mut Solver, parameter: SweepParameter, start: f64, end: f64, steps: usize) -> Sweep {
+    let original_target: Option<Target> = match &parameter {
+        SweepParameter::Target(item_id) => solver.get_targets().get(item_id).cloned(),
+        SweepParameter::ResourceLimit(_) => None,
+    };
+    let original_limit: Option<f64> = match &parameter {
+        SweepParameter::ResourceLimit(item_id) if solver.get_resources().contains(item_id) => Some(solver.get_resources().get_limit(item_id)),
+        _ => None,
+    };
+    let mut points: Vec<SweepPoint> = Vec::new();
+    for step in 0..=steps {
+        let value: f64 = if steps == 0 { start } else { start + (end - start) * step as f64 / steps as f64 };
+        match &parameter {
+            SweepParameter::Target(item_id) => {
+                let target: Target = get_swept_target(original_target, value);
+                solver.set_target(item_id, target);
+            }
+            SweepParameter::ResourceLimit(item_id) => solver.set_resource_limit(item_id, value),
+        }
+        let mut point: SweepPoint = get_sweep_point(solver, value);
+        if let Some(previous) = points.last() {
+            point.mix_changed = previous.feasible != point.feasible || previous.recipes != point.recipes;
+        }
+        points.push(point);
+    }
+    match &parameter {
+        SweepParameter::Target(item_id) => match original_target {
+            Some(target) => solver.set_target(item_id, target),
+            None => solver.remove_target(item_id),
+        },
+        SweepParameter::ResourceLimit(item_id) => match original_limit {
+            Some(limit) => solver.set_resource_limit(item_id, limit),
+            None => solver.remove_resource(item_id),
+        },
+    }
+    return Sweep { parameter, points };
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::ops::Index;
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
+
+use crate::exact::{ExactProblem, ExactSolution};
+use crate::milp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Variable(usize);
+
+impl Variable {
+    pub fn idx(&self) -> usize {
+        return self.0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComparisonOp {
+    Eq,
+    Le,
+    Ge,
+}
+
+//NodeLimit is a branch and bound search that stopped before finding any integer solution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolveError {
+    Infeasible,
+    Unbounded,
+    NodeLimit,
+}
+
+impl From<minilp::Error> for SolveError {
+    fn from(error: minilp::Error) -> SolveError {
+        return match error {
+            minilp::Error::Infeasible => SolveError::Infeasible,
+            minilp::Error::Unbounded => SolveError::Unbounded,
+        };
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    pub name: String,
+    pub terms: Vec<(Variable, f64)>,
+    pub op: ComparisonOp,
+    pub rhs: f64,
+}
+
+//A minimization problem described independently of any solver library
+#[derive(Debug, Clone)]
+pub struct LinearProgram {
+    names: Vec<String>,
+    objective: Vec<f64>,
+    bounds: Vec<(f64, f64)>,
+    constraints: Vec<Constraint>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelStats {
+    pub variables: usize,
+    pub constraints: usize,
+    pub nonzeros: usize,
+    pub integer_variables: usize,
+}
+
+impl Default for LinearProgram {
+    fn default() -> LinearProgram {
+        return LinearProgram::new();
+    }
+}
+
+impl LinearProgram {
+    pub fn new() -> LinearProgram {
+        return LinearProgram {
+            names: Vec::new(),
+            objective: Vec::new(),
+            bounds: Vec::new(),
+            constraints: Vec::new(),
+        };
+    }
+
+    //Names are only used when exporting the model and don't need to be unique
+    pub fn add_var(&mut self, name: &str, objective: f64, bounds: (f64, f64)) -> Variable {
+        self.names.push(name.to_string());
+        self.objective.push(objective);
+        self.bounds.push(bounds);
+        return Variable(self.objective.len() - 1);
+    }
+
+    //Repeated variables are merged, since backends expect each variable once per constraint
+    pub fn add_constraint(&mut self, name: &str, terms: Vec<(Variable, f64)>, op: ComparisonOp, rhs: f64) -> () {
+        let mut merged: Vec<(Variable, f64)> = Vec::new();
+        let mut positions: HashMap<Variable, usize> = HashMap::new();
+        for (variable, coefficient) in terms {
+            match positions.get(&variable) {
+                Some(&position) => merged[position].1 += coefficient,
+                None => {
+                    positions.insert(variable, merged.len());
+                    merged.push((variable, coefficient));
+                }
+            }
+        }
+        self.constraints.push(Constraint { name: name.to_string(), terms: merged, op, rhs });
+    }
+
+    pub fn num_vars(&self) -> usize {
+        return self.objective.len();
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        return self.constraints.len();
+    }
+
+    pub fn get_name(&self, variable: Variable) -> &str {
+        return &self.names[variable.0];
+    }
+
+    pub fn get_objective(&self, variable: Variable) -> f64 {
+        return self.objective[variable.0];
+    }
+
+    pub fn get_bounds(&self, variable: Variable) -> (f64, f64) {
+        return self.bounds[variable.0];
+    }
+
+    pub fn variables(&self) -> Vec<Variable> {
+        return (0..self.objective.len()).map(Variable).collect::<Vec<Variable>>();
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        return &self.constraints;
+    }
+
+    pub fn stats(&self, integer_variables: &[Variable]) -> ModelStats {
+        return ModelStats {
+            variables: self.num_vars(),
+            constraints: self.num_constraints(),
+            nonzeros: self.constraints.iter().map(|constraint| constraint.terms.len()).sum(),
+            integer_variables: integer_variables.len(),
+        };
+    }
+}
+
+//Duals are per constraint in the order they were added, when the backend can provide them.
+//A solution isn't optimal when an integer search stopped early and returned the best plan it had found
+#[derive(Debug, Clone)]
+pub struct LpSolution {
+    pub objective: f64,
+    pub values: Vec<f64>,
+    pub exact_values: Option<Vec<BigRational>>,
+    pub duals: Option<Vec<f64>>,
+    pub optimal: bool,
+}
+
+impl LpSolution {
+    pub fn get_exact(&self, variable: Variable) -> Option<BigRational> {
+        return self.exact_values.as_ref().map(|values| values[variable.0].clone());
+    }
+
+    pub fn get_dual(&self, constraint: usize) -> Option<f64> {
+        return self.duals.as_ref().map(|duals| duals[constraint]);
+    }
+}
+
+impl Index<Variable> for LpSolution {
+    type Output = f64;
+
+    fn index(&self, variable: Variable) -> &f64 {
+        return &self.values[variable.0];
+    }
+}
+
+pub trait LpBackend: Debug {
+    #[allow(dead_code)]
+    fn name(&self) -> &str;
+
+    //Integer variables must take whole values; backends without integer support may round-trip
+    //through another backend as long as the result respects them
+    fn solve(&self, program: &LinearProgram, integer_variables: &[Variable]) -> Result<LpSolution, SolveError>;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MinilpBackend;
+
+impl LpBackend for MinilpBackend {
+    fn name(&self) -> &str {
+        return "minilp";
+    }
+
+    //minilp doesn't expose duals
+    fn solve(&self, program: &LinearProgram, integer_variables: &[Variable]) -> Result<LpSolution, SolveError> {
+        let mut problem: minilp::Problem = minilp::Problem::new(minilp::OptimizationDirection::Minimize);
+        let variables: Vec<minilp::Variable> = program.variables().iter().map(|&variable| problem.add_var(program.get_objective(variable), program.get_bounds(variable))).collect::<Vec<minilp::Variable>>();
+        for constraint in program.constraints() {
+            let op: minilp::ComparisonOp = match constraint.op {
+                ComparisonOp::Eq => minilp::ComparisonOp::Eq,
+                ComparisonOp::Le => minilp::ComparisonOp::Le,
+                ComparisonOp::Ge => minilp::ComparisonOp::Ge,
+            };
+            problem.add_constraint(constraint.terms.iter().map(|&(variable, coefficient)| (variables[variable.0], coefficient)).collect::<Vec<(minilp::Variable, f64)>>(), op, constraint.rhs);
+        }
+        let integer_variables: Vec<minilp::Variable> = integer_variables.iter().map(|variable| variables[variable.0]).collect::<Vec<minilp::Variable>>();
+        let (solution, optimal): (minilp::Solution, bool) = if integer_variables.is_empty() {
+            (problem.solve()?, true)
+        } else {
+            let integer_solution: milp::IntegerSolution = milp::solve_integer(&problem, &integer_variables)?;
+            (integer_solution.solution, integer_solution.optimal)
+        };
+        return Ok(LpSolution {
+            objective: solution.objective(),
+            values: variables.iter().map(|&variable| solution[variable]).collect::<Vec<f64>>(),
+            exact_values: None,
+            duals: None,
+            optimal,
+        });
+    }
+}
+
+//Solves over big-integer rationals; integer variables are fixed to the counts found by minilp's branch and bound
+#[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
+pub struct ExactBackend;
+
+impl LpBackend for ExactBackend {
+    fn name(&self) -> &str {
+        return "exact";
+    }
+
+    fn solve(&self, program: &LinearProgram, integer_variables: &[Variable]) -> Result<LpSolution, SolveError> {
+        let mut problem: ExactProblem = ExactProblem::new();
+        for variable in program.variables() {
+            problem.add_var(program.get_objective(variable), program.get_bounds(variable));
+        }
+        for constraint in program.constraints() {
+            problem.add_constraint(&constraint.terms.iter().map(|&(variable, coefficient)| (variable.0, coefficient)).collect::<Vec<(usize, f64)>>(), constraint.op, constraint.rhs);
+        }
+        let mut optimal: bool = true;
+        if !integer_variables.is_empty() {
+            let relaxed: LpSolution = MinilpBackend.solve(program, integer_variables)?;
+            optimal = relaxed.optimal;
+            for &variable in integer_variables {
+                problem.add_constraint(&[(variable.0, 1.0)], ComparisonOp::Eq, relaxed[variable].round());
+            }
+        }
+        let solution: ExactSolution = problem.solve()?;
+        let duals: Vec<f64> = solution.duals[..program.num_constraints()].iter().map(|dual| dual.to_f64().unwrap()).collect::<Vec<f64>>();
+        return Ok(LpSolution {
+            objective: solution.objective.to_f64().unwrap(),
+            values: solution.values.iter().map(|value| value.to_f64().unwrap()).collect::<Vec<f64>>(),
+            exact_values: Some(solution.values),
+            duals: Some(duals),
+            optimal,
+        });
+    }
+}
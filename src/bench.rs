@@ -0,0 +1,62 @@
+use std::collections::{HashMap, HashSet};
+use std::hint;
+use std::time::{Duration, Instant};
+
+use crate::backend::ModelStats;
+use crate::json::GameData;
+use crate::solver::Solver;
+
+#[derive(Debug, Clone)]
+pub struct BenchmarkResult {
+    pub targets: usize,
+    pub stats: ModelStats,
+    pub build_time: Duration,
+    pub creator_lookups: usize,
+    pub creator_lookup_time: Duration,
+}
+
+//Every craftable item in id order, so runs with the same data always pick the same targets
+fn get_craftable_items(data: &GameData) -> Vec<String> {
+    let mut item_ids: Vec<String> = data.items.keys().filter(|&item_id| !data.get_item_creators(item_id, &HashSet::new()).is_empty()).cloned().collect::<Vec<String>>();
+    item_ids.sort();
+    return item_ids;
+}
+
+//Every build first checks each recipe's ingredients for a recipe that makes them,
+//and each of those lookups scans every recipe, so this part doesn't shrink with the targets
+fn time_creator_lookups(data: &GameData) -> (usize, Duration) {
+    let mut lookups: usize = 0;
+    let start: Instant = Instant::now();
+    for recipe_id in data.recipes.keys() {
+        for ingredient in data.get_ingredients(recipe_id) {
+            hint::black_box(data.get_item_creators(&ingredient, &HashSet::new()));
+            lookups += 1;
+        }
+    }
+    return (lookups, start.elapsed());
+}
+
+//Times the whole model construction next to the recipe creator lookups it starts with,
+//taking the fastest of several runs; target sets larger than the number of craftable items are capped
+pub fn benchmark_model_construction(data: &GameData, target_counts: &[usize], runs: usize) -> Vec<BenchmarkResult> {
+    let item_ids: Vec<String> = get_craftable_items(data);
+    let mut results: Vec<BenchmarkResult> = Vec::new();
+    for &count in target_counts {
+        let targets: HashMap<String, f64> = item_ids.iter().take(count).map(|item_id| (item_id.clone(), 1.0)).collect::<HashMap<String, f64>>();
+        let mut build_time: Duration = Duration::MAX;
+        let mut creator_lookups: usize = 0;
+        let mut creator_lookup_time: Duration = Duration::MAX;
+        let mut stats: Option<ModelStats> = None;
+        for _ in 0..runs.max(1) {
+            let mut solver: Solver = Solver::new(data.clone(), targets.clone());
+            let start: Instant = Instant::now();
+            stats = Some(solver.get_model_stats());
+            build_time = build_time.min(start.elapsed());
+            let (lookups, lookup_time): (usize, Duration) = time_creator_lookups(data);
+            creator_lookups = lookups;
+            creator_lookup_time = creator_lookup_time.min(lookup_time);
+        }
+        results.push(BenchmarkResult { targets: targets.len(), stats: stats.unwrap(), build_time, creator_lookups, creator_lookup_time });
+    }
+    return results;
+}
@@ -0,0 +1,395 @@
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed, Zero};
+
+use crate::backend::{ComparisonOp, SolveError};
+use crate::numeric;
+
+const DEGENERATE_PIVOT_LIMIT: usize = 50;
+
+//Coefficients come from recipe amounts and times, so they are snapped to the fraction they stand for
+//(1/3 rather than 0.333...); anything else is taken as the exact value of the float
+pub fn to_rational(value: f64) -> BigRational {
+    return match numeric::to_fraction(value) {
+        Some((numerator, denominator)) => BigRational::new(BigInt::from(numerator), BigInt::from(denominator)),
+        None => BigRational::from_float(value).unwrap(),
+    };
+}
+
+#[derive(Debug, Clone)]
+struct ExactConstraint {
+    terms: Vec<(usize, BigRational)>,
+    op: ComparisonOp,
+    rhs: BigRational,
+}
+
+//A minimization problem with variables bounded below by zero, solved by two-phase tableau simplex
+#[derive(Debug, Clone)]
+pub struct ExactProblem {
+    objective: Vec<BigRational>,
+    upper_bounds: Vec<Option<BigRational>>,
+    constraints: Vec<ExactConstraint>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExactSolution {
+    pub objective: BigRational,
+    pub values: Vec<BigRational>,
+    pub duals: Vec<BigRational>,
+}
+
+struct Tableau {
+    rows: Vec<Vec<BigRational>>,
+    costs: Vec<BigRational>,
+    basis: Vec<usize>,
+    allowed_columns: usize,
+}
+
+impl Tableau {
+    fn pivot(&mut self, pivot_row: usize, pivot_column: usize) -> () {
+        let pivot: BigRational = self.rows[pivot_row][pivot_column].clone();
+        for value in self.rows[pivot_row].iter_mut().filter(|value| !value.is_zero()) {
+            *value /= &pivot;
+        }
+        let nonzero: Vec<(usize, BigRational)> = self.rows[pivot_row].iter().enumerate().filter(|(_, value)| !value.is_zero()).map(|(column, value)| (column, value.clone())).collect::<Vec<(usize, BigRational)>>();
+        for (row_index, row) in self.rows.iter_mut().enumerate() {
+            if row_index == pivot_row || row[pivot_column].is_zero() {
+                continue;
+            }
+            let factor: BigRational = row[pivot_column].clone();
+            for (column, value) in nonzero.iter() {
+                row[*column] -= &factor * value;
+            }
+        }
+        if !self.costs[pivot_column].is_zero() {
+            let factor: BigRational = self.costs[pivot_column].clone();
+            for (column, value) in nonzero.iter() {
+                self.costs[*column] -= &factor * value;
+            }
+        }
+        self.basis[pivot_row] = pivot_column;
+    }
+
+    //Dantzig's rule normally, Bland's rule after a run of degenerate pivots so the method can't cycle
+    fn get_entering_column(&self, use_bland: bool) -> Option<usize> {
+        let mut best: Option<usize> = None;
+        for column in 0..self.allowed_columns {
+            if !self.costs[column].is_negative() {
+                continue;
+            }
+            if use_bland {
+                return Some(column);
+            }
+            if best.is_none_or(|best_column| self.costs[column] < self.costs[best_column]) {
+                best = Some(column);
+            }
+        }
+        return best;
+    }
+
+    fn get_leaving_row(&self, column: usize) -> Option<usize> {
+        let rhs_column: usize = self.costs.len() - 1;
+        let mut best: Option<(usize, BigRational)> = None;
+        for (row_index, row) in self.rows.iter().enumerate() {
+            if !row[column].is_positive() {
+                continue;
+            }
+            let ratio: BigRational = &row[rhs_column] / &row[column];
+            let better: bool = match &best {
+                None => true,
+                Some((best_row, best_ratio)) => ratio < *best_ratio || (ratio == *best_ratio && self.basis[row_index] < self.basis[*best_row]),
+            };
+            if better {
+                best = Some((row_index, ratio));
+            }
+        }
+        return best.map(|(row_index, _)| row_index);
+    }
+
+    fn optimize(&mut self) -> Result<(), SolveError> {
+        let rhs_column: usize = self.costs.len() - 1;
+        let mut degenerate_pivots: usize = 0;
+        loop {
+            let column: usize = match self.get_entering_column(degenerate_pivots >= DEGENERATE_PIVOT_LIMIT) {
+                Some(column) => column,
+                None => return Ok(()),
+            };
+            let row: usize = self.get_leaving_row(column).ok_or(SolveError::Unbounded)?;
+            if self.rows[row][rhs_column].is_zero() {
+                degenerate_pivots += 1;
+            } else {
+                degenerate_pivots = 0;
+            }
+            self.pivot(row, column);
+        }
+    }
+
+    fn set_costs(&mut self, column_costs: &[BigRational]) -> () {
+        let rhs_column: usize = self.costs.len() - 1;
+        self.costs = vec![BigRational::zero(); rhs_column + 1];
+        for (column, cost) in column_costs.iter().enumerate() {
+            self.costs[column] = cost.clone();
+        }
+        for (row_index, row) in self.rows.iter().enumerate() {
+            let basic_cost: &BigRational = &self.costs[self.basis[row_index]].clone();
+            if basic_cost.is_zero() {
+                continue;
+            }
+            for (column, value) in row.iter().enumerate().filter(|(_, value)| !value.is_zero()) {
+                self.costs[column] -= basic_cost * value;
+            }
+        }
+    }
+}
+
+impl Default for ExactProblem {
+    fn default() -> ExactProblem {
+        return ExactProblem::new();
+    }
+}
+
+impl ExactProblem {
+    pub fn new() -> ExactProblem {
+        return ExactProblem {
+            objective: Vec::new(),
+            upper_bounds: Vec::new(),
+            constraints: Vec::new(),
+        };
+    }
+
+    pub fn add_var(&mut self, objective: f64, (min, max): (f64, f64)) -> usize {
+        if min != 0.0 {
+            panic!("Exact solving only supports variables with a lower bound of zero");
+        }
+        self.objective.push(to_rational(objective));
+        self.upper_bounds.push(if max.is_finite() { Some(to_rational(max)) } else { None });
+        return self.objective.len() - 1;
+    }
+
+    pub fn add_constraint(&mut self, terms: &[(usize, f64)], op: ComparisonOp, rhs: f64) -> () {
+        let terms: Vec<(usize, BigRational)> = terms.iter().map(|&(variable, coefficient)| (variable, to_rational(coefficient))).collect::<Vec<(usize, BigRational)>>();
+        self.constraints.push(ExactConstraint { terms, op, rhs: to_rational(rhs) });
+    }
+
+    pub fn num_vars(&self) -> usize {
+        return self.objective.len();
+    }
+
+    //Rows are flipped so every right hand side is non-negative; <= rows start with their slack in the basis,
+    //>= and = rows with an artificial variable that phase one drives to zero
+    pub fn solve(&self) -> Result<ExactSolution, SolveError> {
+        let mut constraints: Vec<ExactConstraint> = self.constraints.clone();
+        for (variable, bound) in self.upper_bounds.iter().enumerate() {
+            if let Some(bound) = bound {
+                constraints.push(ExactConstraint { terms: vec![(variable, BigRational::from_integer(BigInt::from(1)))], op: ComparisonOp::Le, rhs: bound.clone() });
+            }
+        }
+        let flipped: Vec<bool> = constraints.iter().map(|constraint| constraint.rhs.is_negative()).collect::<Vec<bool>>();
+        for constraint in constraints.iter_mut().filter(|constraint| constraint.rhs.is_negative()) {
+            constraint.rhs = -constraint.rhs.clone();
+            for (_, coefficient) in constraint.terms.iter_mut() {
+                *coefficient = -coefficient.clone();
+            }
+            constraint.op = match constraint.op {
+                ComparisonOp::Le => ComparisonOp::Ge,
+                ComparisonOp::Ge => ComparisonOp::Le,
+                ComparisonOp::Eq => ComparisonOp::Eq,
+            };
+        }
+        let num_vars: usize = self.num_vars();
+        let num_slacks: usize = constraints.iter().filter(|constraint| !matches!(constraint.op, ComparisonOp::Eq)).count();
+        let num_artificials: usize = constraints.iter().filter(|constraint| !matches!(constraint.op, ComparisonOp::Le)).count();
+        let artificial_start: usize = num_vars + num_slacks;
+        let rhs_column: usize = artificial_start + num_artificials;
+        let mut tableau: Tableau = Tableau { rows: Vec::new(), costs: vec![BigRational::zero(); rhs_column + 1], basis: Vec::new(), allowed_columns: rhs_column };
+        let (mut next_slack, mut next_artificial): (usize, usize) = (num_vars, artificial_start);
+        //The column whose reduced cost gives each row's dual, and the sign relating them
+        let mut dual_columns: Vec<(usize, bool)> = Vec::new();
+        for constraint in constraints.iter() {
+            let mut row: Vec<BigRational> = vec![BigRational::zero(); rhs_column + 1];
+            for (variable, coefficient) in constraint.terms.iter() {
+                row[*variable] += coefficient;
+            }
+            row[rhs_column] = constraint.rhs.clone();
+            let one: BigRational = BigRational::from_integer(BigInt::from(1));
+            match constraint.op {
+                ComparisonOp::Le => {
+                    dual_columns.push((next_slack, false));
+                    row[next_slack] = one;
+                    tableau.basis.push(next_slack);
+                    next_slack += 1;
+                }
+                ComparisonOp::Ge => {
+                    dual_columns.push((next_slack, true));
+                    row[next_slack] = -one.clone();
+                    row[next_artificial] = one;
+                    tableau.basis.push(next_artificial);
+                    next_slack += 1;
+                    next_artificial += 1;
+                }
+                ComparisonOp::Eq => {
+                    dual_columns.push((next_artificial, false));
+                    row[next_artificial] = one;
+                    tableau.basis.push(next_artificial);
+                    next_artificial += 1;
+                }
+            }
+            tableau.rows.push(row);
+        }
+        let mut phase_one_costs: Vec<BigRational> = vec![BigRational::zero(); rhs_column];
+        for cost in phase_one_costs[artificial_start..].iter_mut() {
+            *cost = BigRational::from_integer(BigInt::from(1));
+        }
+        tableau.set_costs(&phase_one_costs);
+        tableau.optimize()?;
+        if tableau.costs[rhs_column].is_negative() {
+            return Err(SolveError::Infeasible);
+        }
+        //Artificials left in the basis are at zero; swap them out, or drop their row if it is redundant
+        let mut row_index: usize = 0;
+        while row_index < tableau.rows.len() {
+            if tableau.basis[row_index] < artificial_start {
+                row_index += 1;
+                continue;
+            }
+            match (0..artificial_start).find(|&column| !tableau.rows[row_index][column].is_zero()) {
+                Some(column) => {
+                    tableau.pivot(row_index, column);
+                    row_index += 1;
+                }
+                None => {
+                    tableau.rows.remove(row_index);
+                    tableau.basis.remove(row_index);
+                }
+            }
+        }
+        tableau.allowed_columns = artificial_start;
+        tableau.set_costs(&self.objective);
+        tableau.optimize()?;
+        let mut values: Vec<BigRational> = vec![BigRational::zero(); num_vars];
+        for (row_index, &column) in tableau.basis.iter().enumerate() {
+            if column < num_vars {
+                values[column] = tableau.rows[row_index][rhs_column].clone();
+            }
+        }
+        let objective: BigRational = values.iter().zip(self.objective.iter()).fold(BigRational::zero(), |acc, (value, cost)| acc + value * cost);
+        let mut duals: Vec<BigRational> = Vec::new();
+        for (&(column, positive), &was_flipped) in dual_columns.iter().zip(flipped.iter()) {
+            let dual: BigRational = if positive { tableau.costs[column].clone() } else { -tableau.costs[column].clone() };
+            duals.push(if was_flipped { -dual } else { dual });
+        }
+        return Ok(ExactSolution { objective, values, duals });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fraction(numerator: i64, denominator: i64) -> BigRational {
+        return BigRational::new(BigInt::from(numerator), BigInt::from(denominator));
+    }
+
+    #[test]
+    fn solves_ge_rows_with_positive_duals() {
+        //min x + y, x + 2y >= 4, 3x + y >= 6: optimum at (8/5, 6/5)
+        let mut problem: ExactProblem = ExactProblem::new();
+        let x: usize = problem.add_var(1.0, (0.0, f64::INFINITY));
+        let y: usize = problem.add_var(1.0, (0.0, f64::INFINITY));
+        problem.add_constraint(&[(x, 1.0), (y, 2.0)], ComparisonOp::Ge, 4.0);
+        problem.add_constraint(&[(x, 3.0), (y, 1.0)], ComparisonOp::Ge, 6.0);
+        let solution: ExactSolution = problem.solve().unwrap();
+        assert_eq!(solution.objective, fraction(14, 5));
+        assert_eq!(solution.values, vec![fraction(8, 5), fraction(6, 5)]);
+        assert_eq!(solution.duals, vec![fraction(2, 5), fraction(1, 5)]);
+    }
+
+    #[test]
+    fn solves_eq_rows_with_dual_signs() {
+        //min x, x + y = 3, y <= 1: raising the = row costs 1, raising the <= row saves 1
+        let mut problem: ExactProblem = ExactProblem::new();
+        let x: usize = problem.add_var(1.0, (0.0, f64::INFINITY));
+        let y: usize = problem.add_var(0.0, (0.0, f64::INFINITY));
+        problem.add_constraint(&[(x, 1.0), (y, 1.0)], ComparisonOp::Eq, 3.0);
+        problem.add_constraint(&[(y, 1.0)], ComparisonOp::Le, 1.0);
+        let solution: ExactSolution = problem.solve().unwrap();
+        assert_eq!(solution.objective, fraction(2, 1));
+        assert_eq!(solution.values, vec![fraction(2, 1), fraction(1, 1)]);
+        assert_eq!(solution.duals, vec![fraction(1, 1), fraction(-1, 1)]);
+    }
+
+    #[test]
+    fn flips_negative_right_hand_sides() {
+        //-x <= -2 is x >= 2 once flipped; the dual is still relative to the row as written
+        let mut problem: ExactProblem = ExactProblem::new();
+        let x: usize = problem.add_var(1.0, (0.0, f64::INFINITY));
+        problem.add_constraint(&[(x, -1.0)], ComparisonOp::Le, -2.0);
+        let solution: ExactSolution = problem.solve().unwrap();
+        assert_eq!(solution.values, vec![fraction(2, 1)]);
+        assert_eq!(solution.duals, vec![fraction(-1, 1)]);
+    }
+
+    #[test]
+    fn uses_upper_bounds() {
+        let mut problem: ExactProblem = ExactProblem::new();
+        let x: usize = problem.add_var(-1.0, (0.0, 5.0));
+        let y: usize = problem.add_var(-1.0, (0.0, f64::INFINITY));
+        problem.add_constraint(&[(x, 1.0), (y, 1.0)], ComparisonOp::Le, 7.0);
+        problem.add_constraint(&[(y, 1.0)], ComparisonOp::Le, 1.0);
+        let solution: ExactSolution = problem.solve().unwrap();
+        assert_eq!(solution.objective, fraction(-6, 1));
+        assert_eq!(solution.values, vec![fraction(5, 1), fraction(1, 1)]);
+        assert_eq!(solution.duals.len(), 3);
+    }
+
+    #[test]
+    fn drops_redundant_rows() {
+        let mut problem: ExactProblem = ExactProblem::new();
+        let x: usize = problem.add_var(1.0, (0.0, f64::INFINITY));
+        let y: usize = problem.add_var(0.0, (0.0, f64::INFINITY));
+        problem.add_constraint(&[(x, 1.0), (y, 1.0)], ComparisonOp::Eq, 2.0);
+        problem.add_constraint(&[(x, 2.0), (y, 2.0)], ComparisonOp::Eq, 4.0);
+        let solution: ExactSolution = problem.solve().unwrap();
+        assert_eq!(solution.values, vec![fraction(0, 1), fraction(2, 1)]);
+    }
+
+    #[test]
+    fn reports_infeasible() {
+        let mut problem: ExactProblem = ExactProblem::new();
+        let x: usize = problem.add_var(1.0, (0.0, f64::INFINITY));
+        problem.add_constraint(&[(x, 1.0)], ComparisonOp::Le, 1.0);
+        problem.add_constraint(&[(x, 1.0)], ComparisonOp::Ge, 2.0);
+        assert_eq!(problem.solve().unwrap_err(), SolveError::Infeasible);
+    }
+
+    #[test]
+    fn reports_unbounded() {
+        let mut problem: ExactProblem = ExactProblem::new();
+        let x: usize = problem.add_var(-1.0, (0.0, f64::INFINITY));
+        let y: usize = problem.add_var(0.0, (0.0, f64::INFINITY));
+        problem.add_constraint(&[(x, 1.0), (y, -1.0)], ComparisonOp::Le, 1.0);
+        assert_eq!(problem.solve().unwrap_err(), SolveError::Unbounded);
+    }
+
+    #[test]
+    fn terminates_on_degenerate_problems() {
+        //Beale's example, the textbook case of Dantzig's rule cycling on degenerate pivots
+        let mut problem: ExactProblem = ExactProblem::new();
+        let x4: usize = problem.add_var(-0.75, (0.0, f64::INFINITY));
+        let x5: usize = problem.add_var(20.0, (0.0, f64::INFINITY));
+        let x6: usize = problem.add_var(-0.5, (0.0, f64::INFINITY));
+        let x7: usize = problem.add_var(6.0, (0.0, f64::INFINITY));
+        problem.add_constraint(&[(x4, 0.25), (x5, -8.0), (x6, -1.0), (x7, 9.0)], ComparisonOp::Le, 0.0);
+        problem.add_constraint(&[(x4, 0.5), (x5, -12.0), (x6, -0.5), (x7, 3.0)], ComparisonOp::Le, 0.0);
+        problem.add_constraint(&[(x6, 1.0)], ComparisonOp::Le, 1.0);
+        let solution: ExactSolution = problem.solve().unwrap();
+        assert_eq!(solution.objective, fraction(-5, 4));
+    }
+
+    #[test]
+    fn converts_recipe_rates_exactly() {
+        assert_eq!(to_rational(1.0 / 3.0), fraction(1, 3));
+        assert_eq!(to_rational(0.1), fraction(1, 10));
+        assert_eq!(to_rational(std::f64::consts::PI), BigRational::from_float(std::f64::consts::PI).unwrap());
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::backend::{ComparisonOp, LinearProgram, Variable};
+
+const LP_LINE_LENGTH: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModelFormat {
+    Lp,
+    Mps,
+}
+
+//Both formats end names at whitespace and LP treats operators as separators, so anything else is replaced
+fn sanitize_name(name: &str) -> String {
+    let mut sanitized: String = name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '.' { c } else { '_' }).collect::<String>();
+    if sanitized.is_empty() || sanitized.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        sanitized.insert(0, '_');
+    }
+    return sanitized;
+}
+
+//Model names are only meant to be readable, so duplicates get a numbered suffix
+fn get_unique_names<'a>(names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut used: HashSet<String> = HashSet::new();
+    let mut unique_names: Vec<String> = Vec::new();
+    for name in names {
+        let base: String = sanitize_name(name);
+        let mut unique: String = base.clone();
+        let mut suffix: usize = 1;
+        while used.contains(&unique) {
+            unique = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        used.insert(unique.clone());
+        unique_names.push(unique);
+    }
+    return unique_names;
+}
+
+fn get_variable_names(program: &LinearProgram) -> Vec<String> {
+    return get_unique_names(program.variables().into_iter().map(|variable| program.get_name(variable)));
+}
+
+fn get_constraint_names(program: &LinearProgram) -> Vec<String> {
+    return get_unique_names(program.constraints().iter().map(|constraint| constraint.name.as_str()));
+}
+
+fn format_number(value: f64) -> String {
+    return format!("{}", value);
+}
+
+//LP expressions can't be empty, so a zero term on the first variable stands in
+fn write_lp_expression(output: &mut String, terms: &[(Variable, f64)], variable_names: &[String]) -> () {
+    let mut line_length: usize = 0;
+    let mut first: bool = true;
+    for &(variable, coefficient) in terms.iter().filter(|&&(_, coefficient)| coefficient != 0.0) {
+        let sign: &str = if coefficient < 0.0 { "-" } else if first { "" } else { "+" };
+        let term: String = format!("{} {} {}", sign, format_number(coefficient.abs()), variable_names[variable.idx()]);
+        if line_length + term.len() > LP_LINE_LENGTH {
+            output.push_str("\n   ");
+            line_length = 0;
+        }
+        output.push(' ');
+        output.push_str(term.trim_start());
+        line_length += term.len() + 1;
+        first = false;
+    }
+    if first {
+        write!(output, " 0 {}", variable_names.first().map_or("x", |name| name.as_str())).unwrap();
+    }
+}
+
+pub fn to_lp(program: &LinearProgram, integer_variables: &[Variable]) -> String {
+    let variable_names: Vec<String> = get_variable_names(program);
+    let constraint_names: Vec<String> = get_constraint_names(program);
+    let mut output: String = String::new();
+    output.push_str("Minimize\n obj:");
+    let objective: Vec<(Variable, f64)> = program.variables().into_iter().map(|variable| (variable, program.get_objective(variable))).collect::<Vec<(Variable, f64)>>();
+    write_lp_expression(&mut output, &objective, &variable_names);
+    output.push_str("\nSubject To\n");
+    for (constraint, name) in program.constraints().iter().zip(constraint_names.iter()) {
+        write!(output, " {}:", name).unwrap();
+        write_lp_expression(&mut output, &constraint.terms, &variable_names);
+        let op: &str = match constraint.op {
+            ComparisonOp::Eq => "=",
+            ComparisonOp::Le => "<=",
+            ComparisonOp::Ge => ">=",
+        };
+        writeln!(output, " {} {}", op, format_number(constraint.rhs)).unwrap();
+    }
+    output.push_str("Bounds\n");
+    for variable in program.variables() {
+        let name: &str = &variable_names[variable.idx()];
+        match program.get_bounds(variable) {
+            (min, max) if min == f64::NEG_INFINITY && max == f64::INFINITY => writeln!(output, " {} free", name).unwrap(),
+            (min, max) if max == f64::INFINITY => writeln!(output, " {} >= {}", name, format_number(min)).unwrap(),
+            (min, max) if min == f64::NEG_INFINITY => writeln!(output, " -inf <= {} <= {}", name, format_number(max)).unwrap(),
+            (min, max) => writeln!(output, " {} <= {} <= {}", format_number(min), name, format_number(max)).unwrap(),
+        }
+    }
+    if !integer_variables.is_empty() {
+        output.push_str("General\n");
+        for variable in integer_variables.iter() {
+            writeln!(output, " {}", variable_names[variable.idx()]).unwrap();
+        }
+    }
+    output.push_str("End\n");
+    return output;
+}
+
+//Free-format MPS, since the names are longer than the fixed format's eight characters
+pub fn to_mps(program: &LinearProgram, integer_variables: &[Variable]) -> String {
+    let variable_names: Vec<String> = get_variable_names(program);
+    let constraint_names: Vec<String> = get_constraint_names(program);
+    let integers: HashSet<Variable> = integer_variables.iter().cloned().collect::<HashSet<Variable>>();
+    let mut columns: Vec<Vec<(usize, f64)>> = vec![Vec::new(); program.num_vars()];
+    for (row, constraint) in program.constraints().iter().enumerate() {
+        for &(variable, coefficient) in constraint.terms.iter().filter(|&&(_, coefficient)| coefficient != 0.0) {
+            columns[variable.idx()].push((row, coefficient));
+        }
+    }
+    let mut output: String = String::new();
+    output.push_str("NAME factory\nROWS\n N obj\n");
+    for (constraint, name) in program.constraints().iter().zip(constraint_names.iter()) {
+        let op: &str = match constraint.op {
+            ComparisonOp::Eq => "E",
+            ComparisonOp::Le => "L",
+            ComparisonOp::Ge => "G",
+        };
+        writeln!(output, " {} {}", op, name).unwrap();
+    }
+    output.push_str("COLUMNS\n");
+    let mut in_integer_block: bool = false;
+    let mut markers: usize = 0;
+    for variable in program.variables() {
+        if integers.contains(&variable) != in_integer_block {
+            in_integer_block = !in_integer_block;
+            let marker: &str = if in_integer_block { "INTORG" } else { "INTEND" };
+            writeln!(output, " MARKER{} 'MARKER' '{}'", markers, marker).unwrap();
+            markers += 1;
+        }
+        let name: &str = &variable_names[variable.idx()];
+        let objective: f64 = program.get_objective(variable);
+        if objective != 0.0 || columns[variable.idx()].is_empty() {
+            writeln!(output, " {} obj {}", name, format_number(objective)).unwrap();
+        }
+        for &(row, coefficient) in columns[variable.idx()].iter() {
+            writeln!(output, " {} {} {}", name, constraint_names[row], format_number(coefficient)).unwrap();
+        }
+    }
+    if in_integer_block {
+        writeln!(output, " MARKER{} 'MARKER' 'INTEND'", markers).unwrap();
+    }
+    output.push_str("RHS\n");
+    for (constraint, name) in program.constraints().iter().zip(constraint_names.iter()).filter(|(constraint, _)| constraint.rhs != 0.0) {
+        writeln!(output, " RHS {} {}", name, format_number(constraint.rhs)).unwrap();
+    }
+    //Integer columns default to a 0-1 range in MPS, so their bounds are always written
+    output.push_str("BOUNDS\n");
+    for variable in program.variables() {
+        let name: &str = &variable_names[variable.idx()];
+        let (min, max): (f64, f64) = program.get_bounds(variable);
+        if min == f64::NEG_INFINITY && max == f64::INFINITY {
+            writeln!(output, " FR BND {}", name).unwrap();
+            continue;
+        }
+        if min == f64::NEG_INFINITY {
+            writeln!(output, " MI BND {}", name).unwrap();
+        } else if min != 0.0 || integers.contains(&variable) {
+            writeln!(output, " LO BND {} {}", name, format_number(min)).unwrap();
+        }
+        if max.is_finite() {
+            writeln!(output, " UP BND {} {}", name, format_number(max)).unwrap();
+        } else if integers.contains(&variable) {
+            writeln!(output, " PL BND {}", name).unwrap();
+        }
+    }
+    output.push_str("ENDATA\n");
+    return output;
+}
+
+pub fn export(program: &LinearProgram, integer_variables: &[Variable], format: ModelFormat) -> String {
+    return match format {
+        ModelFormat::Lp => to_lp(program, integer_variables),
+        ModelFormat::Mps => to_mps(program, integer_variables),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINK: &str = "Recipe_Screw_C__Desc_Screw_C__Recipe_Rotor_C";
+
+    //Two parallel links named alike, an integer building count, a free variable and an integer clock that no row uses
+    fn get_model() -> (LinearProgram, Vec<Variable>) {
+        let mut program: LinearProgram = LinearProgram::new();
+        let link: Variable = program.add_var(LINK, 1.0, (0.0, f64::INFINITY));
+        let buildings: Variable = program.add_var("Buildings_Recipe_Screw_C", 100.0, (0.0, f64::INFINITY));
+        let parallel_link: Variable = program.add_var(LINK, 1.0, (0.0, f64::INFINITY));
+        let import: Variable = program.add_var("Import Desc-Water", 0.0, (f64::NEG_INFINITY, f64::INFINITY));
+        let clock: Variable = program.add_var("Clock", 0.0, (1.0, 2.5));
+        program.add_constraint("Target_Desc_Rotor_C", vec![(link, 1.0), (parallel_link, 1.0)], ComparisonOp::Eq, 20.0);
+        program.add_constraint("Buildings_Recipe_Screw_C", vec![(link, 1.0), (parallel_link, 1.0), (buildings, -40.0)], ComparisonOp::Le, 0.0);
+        program.add_constraint("2 rows", vec![(import, 1.0)], ComparisonOp::Ge, -1.5);
+        program.add_constraint("Target_Desc_Rotor_C", Vec::new(), ComparisonOp::Le, 5.0);
+        return (program, vec![buildings, clock]);
+    }
+
+    #[test]
+    fn sanitizes_names() {
+        assert_eq!(sanitize_name("Recipe_Screw_C"), "Recipe_Screw_C");
+        assert_eq!(sanitize_name("Import Desc-Water"), "Import_Desc_Water");
+        assert_eq!(sanitize_name("2 rows"), "_2_rows");
+        assert_eq!(sanitize_name(".5"), "_.5");
+        assert_eq!(sanitize_name(""), "_");
+    }
+
+    #[test]
+    fn suffixes_duplicate_names() {
+        assert_eq!(get_unique_names(["x", "x", "x_1", "y"].into_iter()), vec!["x", "x_1", "x_1_1", "y"]);
+    }
+
+    #[test]
+    fn writes_lp() {
+        let (program, integer_variables): (LinearProgram, Vec<Variable>) = get_model();
+        let expected: String = format!("\
+Minimize
+ obj: 1 {link} + 100 Buildings_Recipe_Screw_C + 1 {link}_1
+Subject To
+ Target_Desc_Rotor_C: 1 {link} + 1 {link}_1 = 20
+ Buildings_Recipe_Screw_C: 1 {link} + 1 {link}_1 - 40 Buildings_Recipe_Screw_C <= 0
+ _2_rows: 1 Import_Desc_Water >= -1.5
+ Target_Desc_Rotor_C_1: 0 {link} <= 5
+Bounds
+ {link} >= 0
+ Buildings_Recipe_Screw_C >= 0
+ {link}_1 >= 0
+ Import_Desc_Water free
+ 1 <= Clock <= 2.5
+General
+ Buildings_Recipe_Screw_C
+ Clock
+End
+", link = LINK);
+        assert_eq!(export(&program, &integer_variables, ModelFormat::Lp), expected);
+    }
+
+    #[test]
+    fn wraps_long_lp_expressions() {
+        let mut program: LinearProgram = LinearProgram::new();
+        let terms: Vec<(Variable, f64)> = (0..20).map(|_| (program.add_var(LINK, 1.0, (0.0, f64::INFINITY)), 1.0)).collect::<Vec<(Variable, f64)>>();
+        program.add_constraint("Long", terms, ComparisonOp::Le, 1.0);
+        let lp: String = to_lp(&program, &[]);
+        //Only the terms count towards the limit, not the row label in front of them
+        assert!(lp.lines().all(|line| line.len() <= LP_LINE_LENGTH + " Long:".len()));
+        assert!(lp.lines().filter(|line| line.starts_with("   ")).count() > 1);
+    }
+
+    #[test]
+    fn writes_mps() {
+        let (program, integer_variables): (LinearProgram, Vec<Variable>) = get_model();
+        let expected: String = format!("\
+NAME factory
+ROWS
+ N obj
+ E Target_Desc_Rotor_C
+ L Buildings_Recipe_Screw_C
+ G _2_rows
+ L Target_Desc_Rotor_C_1
+COLUMNS
+ {link} obj 1
+ {link} Target_Desc_Rotor_C 1
+ {link} Buildings_Recipe_Screw_C 1
+ MARKER0 'MARKER' 'INTORG'
+ Buildings_Recipe_Screw_C obj 100
+ Buildings_Recipe_Screw_C Buildings_Recipe_Screw_C -40
+ MARKER1 'MARKER' 'INTEND'
+ {link}_1 obj 1
+ {link}_1 Target_Desc_Rotor_C 1
+ {link}_1 Buildings_Recipe_Screw_C 1
+ Import_Desc_Water _2_rows 1
+ MARKER2 'MARKER' 'INTORG'
+ Clock obj 0
+ MARKER3 'MARKER' 'INTEND'
+RHS
+ RHS Target_Desc_Rotor_C 20
+ RHS _2_rows -1.5
+ RHS Target_Desc_Rotor_C_1 5
+BOUNDS
+ LO BND Buildings_Recipe_Screw_C 0
+ PL BND Buildings_Recipe_Screw_C
+ FR BND Import_Desc_Water
+ LO BND Clock 1
+ UP BND Clock 2.5
+ENDATA
+", link = LINK);
+        assert_eq!(export(&program, &integer_variables, ModelFormat::Mps), expected);
+    }
+}
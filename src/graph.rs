@@ -0,0 +1,114 @@
+use std::collections::{HashMap, HashSet};
+
+struct CycleSearch<'a> {
+    edges: &'a HashMap<String, Vec<String>>,
+    index: usize,
+    indices: HashMap<String, usize>,
+    low_links: HashMap<String, usize>,
+    stack: Vec<String>,
+    on_stack: HashSet<String>,
+    cycles: Vec<Vec<String>>,
+}
+
+impl<'a> CycleSearch<'a> {
+    fn new(edges: &'a HashMap<String, Vec<String>>) -> CycleSearch<'a> {
+        return CycleSearch {
+            edges,
+            index: 0,
+            indices: HashMap::new(),
+            low_links: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: HashSet::new(),
+            cycles: Vec::new(),
+        };
+    }
+
+    //Tarjan's strongly connected components
+    fn visit(&mut self, node_id: &str) -> () {
+        self.indices.insert(node_id.to_string(), self.index);
+        self.low_links.insert(node_id.to_string(), self.index);
+        self.index += 1;
+        self.stack.push(node_id.to_string());
+        self.on_stack.insert(node_id.to_string());
+        let edges: &'a HashMap<String, Vec<String>> = self.edges;
+        for next_id in edges.get(node_id).into_iter().flatten() {
+            if !self.indices.contains_key(next_id) {
+                self.visit(next_id);
+                let low_link: usize = self.low_links[node_id].min(self.low_links[next_id]);
+                self.low_links.insert(node_id.to_string(), low_link);
+            } else if self.on_stack.contains(next_id) {
+                let low_link: usize = self.low_links[node_id].min(self.indices[next_id]);
+                self.low_links.insert(node_id.to_string(), low_link);
+            }
+        }
+        if self.low_links[node_id] != self.indices[node_id] {
+            return;
+        }
+        let mut component: Vec<String> = Vec::new();
+        while let Some(member_id) = self.stack.pop() {
+            self.on_stack.remove(&member_id);
+            let done: bool = member_id == node_id;
+            component.push(member_id);
+            if done {
+                break;
+            }
+        }
+        let self_loop: bool = edges.get(node_id).is_some_and(|next_ids| next_ids.iter().any(|next_id| next_id == node_id));
+        if component.len() > 1 || self_loop {
+            component.sort();
+            self.cycles.push(component);
+        }
+    }
+}
+
+//Returns every group of nodes that can reach each other, i.e. the nodes taking part in a loop
+pub fn get_cycles(edges: &HashMap<String, Vec<String>>) -> Vec<Vec<String>> {
+    let mut search: CycleSearch = CycleSearch::new(edges);
+    let mut node_ids: Vec<&String> = edges.keys().collect::<Vec<&String>>();
+    node_ids.sort();
+    for node_id in node_ids {
+        if !search.indices.contains_key(node_id) {
+            search.visit(node_id);
+        }
+    }
+    return search.cycles;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_edges(edges: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        return edges.iter().map(|&(node_id, next_ids)| (node_id.to_string(), next_ids.iter().map(|next_id| next_id.to_string()).collect::<Vec<String>>())).collect::<HashMap<String, Vec<String>>>();
+    }
+
+    fn sorted(mut cycles: Vec<Vec<String>>) -> Vec<Vec<String>> {
+        cycles.sort();
+        return cycles;
+    }
+
+    #[test]
+    fn finds_strongly_connected_components() {
+        let edges: HashMap<String, Vec<String>> = get_edges(&[("a", &["b"]), ("b", &["c"]), ("c", &["a", "d"]), ("d", &["e"]), ("e", &["d", "f"]), ("f", &[])]);
+        assert_eq!(sorted(get_cycles(&edges)), vec![vec!["a", "b", "c"], vec!["d", "e"]]);
+    }
+
+    #[test]
+    fn finds_self_loops() {
+        let edges: HashMap<String, Vec<String>> = get_edges(&[("a", &["a", "b"]), ("b", &[])]);
+        assert_eq!(get_cycles(&edges), vec![vec!["a"]]);
+    }
+
+    #[test]
+    fn ignores_acyclic_graphs() {
+        let edges: HashMap<String, Vec<String>> = get_edges(&[("a", &["b", "c"]), ("b", &["d"]), ("c", &["d"]), ("d", &[])]);
+        assert!(get_cycles(&edges).is_empty());
+        assert!(get_cycles(&HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn follows_nodes_without_edge_entries() {
+        let edges: HashMap<String, Vec<String>> = get_edges(&[("a", &["b"]), ("c", &["c"])]);
+        assert_eq!(get_cycles(&edges), vec![vec!["c"]]);
+    }
+}
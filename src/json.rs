@@ -2,6 +2,8 @@ use std::collections::{HashMap, HashSet};
 use std::fs;
 use serde::Deserialize;
 
+use crate::graph;
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct ItemQuantity {
     pub item: String,
@@ -21,11 +23,24 @@ pub struct Recipe {
     pub products: Vec<ItemQuantity>,
     #[serde(rename = "producedIn")]
     pub produced_in: Vec<String>,
+    #[serde(rename = "isVariablePower")]
+    pub is_variable_power: bool,
+    #[serde(rename = "minPower")]
+    pub min_power: f64,
+    #[serde(rename = "maxPower")]
+    pub max_power: f64,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Item {
     pub name: String,
+    #[serde(rename = "sinkPoints")]
+    pub sink_points: f64,
+    #[serde(rename = "energyValue")]
+    pub energy_value: f64,
+    #[serde(rename = "radioactiveDecay")]
+    pub radioactive_decay: f64,
+    pub liquid: bool,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -34,11 +49,57 @@ pub struct Resource {
     pub name: String,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct Generator {
+    #[serde(rename = "className")]
+    pub class_name: String,
+    pub fuel: Vec<String>,
+    #[serde(rename = "powerProduction")]
+    pub power_production: f64,
+    #[serde(rename = "powerProductionExponent")]
+    #[allow(dead_code)]
+    pub power_production_exponent: f64,
+    #[serde(rename = "waterToPowerRatio")]
+    pub water_to_power_ratio: f64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Miner {
+    #[serde(rename = "className")]
+    pub class_name: String,
+    #[serde(rename = "allowedResources")]
+    pub allowed_resources: Vec<String>,
+    #[serde(rename = "itemsPerCycle")]
+    pub items_per_cycle: f64,
+    #[serde(rename = "extractCycleTime")]
+    pub extract_cycle_time: f64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BuildingMetadata {
+    #[serde(rename = "powerConsumption")]
+    pub power_consumption: f64,
+    #[serde(rename = "powerConsumptionExponent")]
+    pub power_consumption_exponent: f64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Building {
+    pub name: String,
+    #[serde(rename = "className")]
+    #[allow(dead_code)]
+    pub class_name: String,
+    pub metadata: BuildingMetadata,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct GameData {
     pub recipes: HashMap<String, Recipe>,
     pub items: HashMap<String, Item>,
     pub resources: HashMap<String, Resource>,
+    pub generators: HashMap<String, Generator>,
+    pub miners: HashMap<String, Miner>,
+    pub buildings: HashMap<String, Building>,
 }
 
 impl GameData {
@@ -65,6 +126,14 @@ impl GameData {
         return self.recipes.get(recipe_id).unwrap();
     }
 
+    pub fn get_building(&self, building_id: &str) -> &Building {
+        return self.buildings.get(building_id).unwrap();
+    }
+
+    pub fn is_sinkable(&self, item_id: &str) -> bool {
+        return self.get_item(item_id).sink_points > 0.0;
+    }
+
     pub fn get_item_name(&self, item_id: &str) -> String {
         return self.items.get(item_id).unwrap().name.clone();
     }
@@ -73,6 +142,10 @@ impl GameData {
         return self.recipes.get(recipe_id).unwrap().name.clone();
     }
 
+    pub fn get_building_name(&self, building_id: &str) -> String {
+        return self.buildings.get(building_id).unwrap().name.clone();
+    }
+
     pub fn get_ingredients(&self, recipe_id: &str) -> Vec<String> {
         return self.recipes.get(recipe_id).unwrap().ingredients
             .iter()
@@ -87,6 +160,37 @@ impl GameData {
             .collect::<Vec<String>>();
     }
 
+    pub fn get_product_rate(&self, recipe_id: &str, item_id: &str) -> f64 {
+        let recipe: &Recipe = self.get_recipe(recipe_id);
+        for product in recipe.products.iter() {
+            if product.item == item_id {
+                return product.amount * 60.0 / recipe.time;
+            }
+        }
+        return 0.0;
+    }
+
+    pub fn get_ingredient_rate(&self, recipe_id: &str, item_id: &str) -> f64 {
+        let recipe: &Recipe = self.get_recipe(recipe_id);
+        for ingredient in recipe.ingredients.iter() {
+            if ingredient.item == item_id {
+                return ingredient.amount * 60.0 / recipe.time;
+            }
+        }
+        return 0.0;
+    }
+
+    pub fn get_recipe_power(&self, recipe_id: &str) -> f64 {
+        let recipe: &Recipe = self.get_recipe(recipe_id);
+        if recipe.is_variable_power {
+            return (recipe.min_power + recipe.max_power) / 2.0;
+        }
+        return match recipe.produced_in.first() {
+            Some(building_id) => self.get_building(building_id).metadata.power_consumption,
+            None => 0.0,
+        };
+    }
+
     pub fn get_item_creators(&self, item_id: &str, disallowed_recipes: &HashSet<String>) -> Vec<String> {
         let mut creators: Vec<String> = Vec::new();
         for recipe_id in self.recipes.keys().filter(|&recipe_id| !disallowed_recipes.contains(recipe_id)) {
@@ -110,5 +214,18 @@ impl GameData {
         }
         return users;
     }
+
+    //Recipes are linked to every recipe using one of their products
+    pub fn get_recipe_cycles(&self, disallowed_recipes: &HashSet<String>) -> Vec<Vec<String>> {
+        let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+        for recipe_id in self.recipes.keys().filter(|&recipe_id| !disallowed_recipes.contains(recipe_id)) {
+            let mut users: Vec<String> = Vec::new();
+            for product in self.get_products(recipe_id) {
+                users.extend(self.get_item_users(&product, disallowed_recipes));
+            }
+            edges.insert(recipe_id.clone(), users);
+        }
+        return graph::get_cycles(&edges);
+    }
 }
 
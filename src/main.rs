@@ -1,12 +1,30 @@
+#[allow(dead_code)]
+mod analysis;
+mod backend;
+mod bench;
+mod exact;
+#[allow(dead_code)]
+mod export;
+mod graph;
 mod json;
+mod milp;
+mod numeric;
+mod resources;
 mod solver;
 use std::collections::HashMap;
 
+use backend::ModelStats;
 use json::GameData;
-use solver::{Factory, Solver};
+use solver::{Factory, FactorySummary, Solver};
 
 fn main() -> () {
     let data: GameData = GameData::new("./static/data-old.json");
+    if std::env::args().any(|arg| arg == "--bench") {
+        for result in bench::benchmark_model_construction(&data, &[1, 10, 25, 50, 100, 200], 10) {
+            println!("{} targets: {} variables, {} constraints, {} nonzeros in {:.2?}; its {} recipe creator lookups take {:.2?} on their own", result.targets, result.stats.variables, result.stats.constraints, result.stats.nonzeros, result.build_time, result.creator_lookups, result.creator_lookup_time);
+        }
+        return;
+    }
     let mut solver: Solver = Solver::new(data, HashMap::from([
         ("Desc_MotorLightweight_C".to_string(), 20.0),
 
@@ -24,14 +42,44 @@ fn main() -> () {
     let factory: Factory = solver.solve();
     let elapsed = now.elapsed();
     println!("Solve time: {:.2?}", elapsed);
+    if !factory.optimal {
+        println!("Integer search stopped early, a better plan may exist");
+    }
+    let stats: ModelStats = solver.get_model_stats();
+    println!("Model: {} variables ({} integer), {} constraints, {} nonzeros", stats.variables, stats.integer_variables, stats.constraints, stats.nonzeros);
+    let summary: FactorySummary = factory.summary();
+    println!("Power: {:.3} MW consumed, {:.3} MW produced", summary.power_consumption, summary.power_production);
+    for (building_id, count) in summary.machines.iter() {
+        println!("\t{:.3} {}", count, solver.get_data().get_building_name(building_id));
+    }
+    for (label, totals) in [("Resources", &summary.resources), ("Imports", &summary.imports), ("Byproducts", &summary.byproducts), ("Stored", &summary.stored), ("Sunk", &summary.sunk), ("Targets", &summary.targets)] {
+        if totals.is_empty() {
+            continue;
+        }
+        println!("{}:", label);
+        for (item_id, rate) in totals.iter() {
+            println!("\t{} {}", numeric::format_decimal(*rate, 3), solver.get_data().get_item_name(item_id));
+        }
+    }
+    println!("Sink points: {:.3}", summary.sink_points);
+    for violation in factory.verify(solver.get_data()).iter() {
+        println!("Violation: {:?} of {:.6} {} at {}", violation.kind, violation.magnitude, violation.item_id, violation.node_id);
+    }
     for node in factory.nodes.values() {
-        println!("\n{}:\n\tInputs:", node.name);
+        println!("\n{} ({} buildings at {:.3}% using {} shards, {:.3} MW):\n\tInputs:", node.name, node.buildings, node.clock * 100.0, node.shards, node.power);
         for item_rate in node.inputs.values() {
-            println!("\t\t{:.9} {} from node {}", item_rate.rate, item_rate.name, item_rate.other_node_name);
+            println!("\t\t{} {} from node {}", numeric::format_fraction(item_rate.rate, 3), item_rate.name, item_rate.other_node_name);
         }
         println!("\tOutputs:");
         for item_rate in node.outputs.values() {
-            println!("\t\t{:.9} {} to node {} (clock: {:.9})", item_rate.rate, item_rate.name, item_rate.other_node_name, item_rate.underclock);
+            println!("\t\t{} {} to node {}", numeric::format_fraction(item_rate.rate, 3), item_rate.name, item_rate.other_node_name);
+        }
+        if let Some(machines) = &node.machines {
+            let mut groups: Vec<String> = machines.full_machines.iter().map(|&(count, clock)| format!("{} at {:.3}%", count, clock * 100.0)).collect::<Vec<String>>();
+            if let Some(clock) = machines.remainder_clock {
+                groups.push(format!("1 at {:.3}%", clock * 100.0));
+            }
+            println!("\tMachines: {} ({:.3} {}/min each at 100%)", groups.join(" + "), machines.rate_per_machine, solver.get_data().get_item_name(&machines.item_id));
         }
     }
 }
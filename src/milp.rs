@@ -0,0 +1,128 @@
+use minilp::{ComparisonOp, Error, Problem, Solution, Variable};
+
+use crate::backend::SolveError;
+
+const INTEGER_TOLERANCE: f64 = 0.000001;
+const MAX_BRANCH_NODES: usize = 10000;
+
+fn get_fractional_variable(solution: &Solution, integer_variables: &[Variable]) -> Option<(Variable, f64)> {
+    let mut branch: Option<(Variable, f64, f64)> = None;
+    for &variable in integer_variables.iter() {
+        let value: f64 = solution[variable];
+        let distance: f64 = (value - value.round()).abs();
+        if distance <= INTEGER_TOLERANCE {
+            continue;
+        }
+        if branch.is_none_or(|(_, _, best_distance)| distance > best_distance) {
+            branch = Some((variable, value, distance));
+        }
+    }
+    return branch.map(|(variable, value, _)| (variable, value));
+}
+
+pub struct IntegerSolution {
+    pub solution: Solution,
+    pub optimal: bool,
+}
+
+pub fn solve_integer(problem: &Problem, integer_variables: &[Variable]) -> Result<IntegerSolution, SolveError> {
+    return search(problem, integer_variables, MAX_BRANCH_NODES);
+}
+
+//Stops after max_nodes nodes, returning the best solution found so far as not optimal,
+//or an error if there isn't one yet
+fn search(problem: &Problem, integer_variables: &[Variable], max_nodes: usize) -> Result<IntegerSolution, SolveError> {
+    let mut best: Option<Solution> = None;
+    let mut stack: Vec<Solution> = vec![problem.solve()?];
+    let mut explored: usize = 0;
+    while let Some(solution) = stack.pop() {
+        explored += 1;
+        if explored > max_nodes {
+            return match best {
+                Some(solution) => Ok(IntegerSolution { solution, optimal: false }),
+                None => Err(SolveError::NodeLimit),
+            };
+        }
+        if let Some(incumbent) = &best {
+            if solution.objective() >= incumbent.objective() - INTEGER_TOLERANCE {
+                continue;
+            }
+        }
+        match get_fractional_variable(&solution, integer_variables) {
+            Some((variable, value)) => {
+                let down: Result<Solution, Error> = solution.clone().add_constraint([(variable, 1.0)], ComparisonOp::Le, value.floor());
+                let up: Result<Solution, Error> = solution.add_constraint([(variable, 1.0)], ComparisonOp::Ge, value.ceil());
+                //Explore the branch closest to the relaxed value first
+                let (near, far) = if value - value.floor() < 0.5 { (down, up) } else { (up, down) };
+                if let Ok(far_solution) = far {
+                    stack.push(far_solution);
+                }
+                if let Ok(near_solution) = near {
+                    stack.push(near_solution);
+                }
+            }
+            None => best = Some(solution),
+        }
+    }
+    return match best {
+        Some(solution) => Ok(IntegerSolution { solution, optimal: true }),
+        None => Err(SolveError::Infeasible),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minilp::OptimizationDirection;
+
+    //Maximize 5a + 4b + 3c over 0/1 values, whose relaxation is fractional
+    fn get_knapsack() -> (Problem, Vec<Variable>) {
+        let mut problem: Problem = Problem::new(OptimizationDirection::Minimize);
+        let variables: Vec<Variable> = [5.0, 4.0, 3.0].iter().map(|&value| problem.add_var(-value, (0.0, 1.0))).collect::<Vec<Variable>>();
+        for (weights, capacity) in [([2.0, 3.0, 1.0], 5.0), ([4.0, 1.0, 2.0], 11.0), ([3.0, 4.0, 2.0], 8.0)] {
+            problem.add_constraint(variables.iter().cloned().zip(weights).collect::<Vec<(Variable, f64)>>(), ComparisonOp::Le, capacity);
+        }
+        return (problem, variables);
+    }
+
+    #[test]
+    fn solves_knapsack() {
+        let (problem, variables): (Problem, Vec<Variable>) = get_knapsack();
+        assert!(get_fractional_variable(&problem.solve().unwrap(), &variables).is_some());
+        let result: IntegerSolution = solve_integer(&problem, &variables).unwrap();
+        assert!(result.optimal);
+        assert!((result.solution.objective() + 9.0).abs() < 1e-9);
+        let values: Vec<f64> = variables.iter().map(|&variable| result.solution[variable].round()).collect::<Vec<f64>>();
+        assert_eq!(values, vec![1.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn rounds_counts_up() {
+        //A load of 7/3 machines at 100% needs 3 machines
+        let mut problem: Problem = Problem::new(OptimizationDirection::Minimize);
+        let buildings: Variable = problem.add_var(1.0, (0.0, f64::INFINITY));
+        problem.add_constraint([(buildings, 3.0)], ComparisonOp::Ge, 7.0);
+        let result: IntegerSolution = solve_integer(&problem, &[buildings]).unwrap();
+        assert!(result.optimal);
+        assert_eq!(result.solution[buildings], 3.0);
+    }
+
+    #[test]
+    fn reports_infeasible_integer_problems() {
+        let mut problem: Problem = Problem::new(OptimizationDirection::Minimize);
+        let x: Variable = problem.add_var(1.0, (0.0, f64::INFINITY));
+        problem.add_constraint([(x, 2.0)], ComparisonOp::Eq, 3.0);
+        assert!(problem.solve().is_ok());
+        assert_eq!(solve_integer(&problem, &[x]).err(), Some(SolveError::Infeasible));
+    }
+
+    #[test]
+    fn reports_node_limit() {
+        let (problem, variables): (Problem, Vec<Variable>) = get_knapsack();
+        assert_eq!(search(&problem, &variables, 1).err(), Some(SolveError::NodeLimit));
+        let complete: usize = (1..100).find(|&max_nodes| search(&problem, &variables, max_nodes).is_ok_and(|result| result.optimal)).unwrap();
+        let early: Vec<IntegerSolution> = (1..complete).filter_map(|max_nodes| search(&problem, &variables, max_nodes).ok()).collect::<Vec<IntegerSolution>>();
+        assert!(!early.is_empty());
+        assert!(early.iter().all(|result| !result.optimal && get_fractional_variable(&result.solution, &variables).is_none()));
+    }
+}
@@ -0,0 +1,97 @@
+const MAX_DENOMINATOR: u64 = 1000;
+const SNAP_TOLERANCE: f64 = 0.000000001;
+
+//Best rational approximation by continued fractions, accepted only if it is within SNAP_TOLERANCE
+//(relative to the value) so that rates which aren't simple fractions are left untouched
+pub fn to_fraction(value: f64) -> Option<(i64, u64)> {
+    if !value.is_finite() {
+        return None;
+    }
+    let tolerance: f64 = SNAP_TOLERANCE * value.abs().max(1.0);
+    let mut remainder: f64 = value.abs();
+    let (mut numerator, mut previous_numerator): (u64, u64) = (1, 0);
+    let (mut denominator, mut previous_denominator): (u64, u64) = (0, 1);
+    loop {
+        let whole: f64 = remainder.floor();
+        if whole > u64::MAX as f64 / 2.0 {
+            return None;
+        }
+        let next_numerator: u64 = whole as u64 * numerator + previous_numerator;
+        let next_denominator: u64 = whole as u64 * denominator + previous_denominator;
+        if next_denominator > MAX_DENOMINATOR {
+            return None;
+        }
+        (previous_numerator, numerator) = (numerator, next_numerator);
+        (previous_denominator, denominator) = (denominator, next_denominator);
+        if (value.abs() - numerator as f64 / denominator as f64).abs() <= tolerance {
+            let sign: i64 = if value < 0.0 { -1 } else { 1 };
+            return Some((sign * numerator as i64, denominator));
+        }
+        remainder = 1.0 / (remainder - whole);
+    }
+}
+
+pub fn snap(value: f64) -> f64 {
+    return match to_fraction(value) {
+        Some((numerator, denominator)) => numerator as f64 / denominator as f64,
+        None => value,
+    };
+}
+
+//Rounds to at most the given number of decimals and drops trailing zeros, so 7.499999999 shows as 7.5
+pub fn format_decimal(value: f64, decimals: usize) -> String {
+    let text: String = format!("{:.*}", decimals, value);
+    if !text.contains('.') {
+        return text;
+    }
+    let trimmed: &str = text.trim_end_matches('0').trim_end_matches('.');
+    return if trimmed == "-0" { "0".to_string() } else { trimmed.to_string() };
+}
+
+//Shows whole numbers plainly and other rates as improper fractions such as 22/3,
+//falling back to decimals when the value has no small fraction
+pub fn format_fraction(value: f64, decimals: usize) -> String {
+    return match to_fraction(value) {
+        Some((numerator, 1)) => numerator.to_string(),
+        Some((numerator, denominator)) => format!("{}/{}", numerator, denominator),
+        None => format_decimal(value, decimals),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_simple_fractions() {
+        assert_eq!(to_fraction(0.5), Some((1, 2)));
+        assert_eq!(to_fraction(1.0 / 3.0), Some((1, 3)));
+        assert_eq!(to_fraction(4400.0 / 3.0), Some((4400, 3)));
+        assert_eq!(to_fraction(-2.25), Some((-9, 4)));
+        assert_eq!(to_fraction(7.0), Some((7, 1)));
+        assert_eq!(to_fraction(0.0), Some((0, 1)));
+    }
+
+    #[test]
+    fn rejects_values_without_small_fractions() {
+        assert_eq!(to_fraction(std::f64::consts::PI), None);
+        assert_eq!(to_fraction(1.0 / 1009.0), None);
+        assert_eq!(to_fraction(f64::INFINITY), None);
+        assert_eq!(to_fraction(f64::NAN), None);
+    }
+
+    #[test]
+    fn snaps_float_noise() {
+        assert_eq!(snap(0.1 + 0.2), 0.3);
+        assert_eq!(snap(std::f64::consts::E), std::f64::consts::E);
+    }
+
+    #[test]
+    fn formats_rates() {
+        assert_eq!(format_fraction(22.0 / 3.0, 3), "22/3");
+        assert_eq!(format_fraction(30.0, 3), "30");
+        assert_eq!(format_fraction(std::f64::consts::PI, 3), "3.142");
+        assert_eq!(format_decimal(7.499999999, 3), "7.5");
+        assert_eq!(format_decimal(-0.0001, 3), "0");
+    }
+}
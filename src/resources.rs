@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::fs;
+use serde::Deserialize;
+
+const SCARCITY_SCALE: f64 = 10.0;
+const MIN_WEIGHT: f64 = 1.0;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum GameVersion {
+    Update8,
+    Release1,
+}
+
+//Weights set explicitly are kept apart from the one derived from scarcity, so reweighting never overwrites them
+#[derive(Debug, Clone)]
+pub struct Resource {
+    pub limit: f64,
+    pub weight: Option<f64>,
+    pub scarcity_weight: f64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct ResourceEntry {
+    limit: Option<f64>,
+    weight: Option<f64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct ResourceFile {
+    preset: Option<GameVersion>,
+    #[serde(default)]
+    resources: HashMap<String, ResourceEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResourceConfig {
+    resources: HashMap<String, Resource>,
+    scarcity_limit: f64,
+}
+
+impl Default for ResourceConfig {
+    fn default() -> ResourceConfig {
+        return ResourceConfig::new();
+    }
+}
+
+impl ResourceConfig {
+    pub fn new() -> ResourceConfig {
+        return ResourceConfig {
+            resources: HashMap::new(),
+            scarcity_limit: 0.0,
+        };
+    }
+
+    pub fn preset(version: GameVersion) -> ResourceConfig {
+        let limits: Vec<(&str, f64)> = match version {
+            GameVersion::Update8 => vec![
+                ("Desc_Coal_C",         30120.0),
+                ("Desc_LiquidOil_C",    11700.0),
+                ("Desc_NitrogenGas_C",  12000.0),
+                ("Desc_OreBauxite_C",   9780.0),
+                ("Desc_OreCopper_C",    28860.0),
+                ("Desc_OreGold_C",      11040.0),
+                ("Desc_OreIron_C",      70380.0),
+                ("Desc_OreUranium_C",   2100.0),
+                ("Desc_RawQuartz_C",    10500.0),
+                ("Desc_Stone_C",        52860.0),
+                ("Desc_Sulfur_C",       6840.0),
+                ("Desc_Water_C",        f64::INFINITY),
+            ],
+            GameVersion::Release1 => vec![
+                ("Desc_Coal_C",         42300.0),
+                ("Desc_LiquidOil_C",    12600.0),
+                ("Desc_NitrogenGas_C",  12000.0),
+                ("Desc_OreBauxite_C",   12300.0),
+                ("Desc_OreCopper_C",    36900.0),
+                ("Desc_OreGold_C",      15000.0),
+                ("Desc_OreIron_C",      92100.0),
+                ("Desc_OreUranium_C",   2100.0),
+                ("Desc_RawQuartz_C",    13500.0),
+                ("Desc_SAM_C",          10200.0),
+                ("Desc_Stone_C",        69900.0),
+                ("Desc_Sulfur_C",       10800.0),
+                ("Desc_Water_C",        f64::INFINITY),
+            ],
+        };
+        let mut config: ResourceConfig = ResourceConfig::new();
+        for (item_id, limit) in limits {
+            config.set_limit(item_id, limit);
+        }
+        config.set_scarcity_weights();
+        return config;
+    }
+
+    #[allow(dead_code)]
+    pub fn from_file(path: &str) -> ResourceConfig {
+        let config_text: String = fs::read_to_string(path).expect("Couldn't read file");
+        let file: ResourceFile = serde_json::from_str(&config_text).expect("Couldn't parse json");
+        let mut config: ResourceConfig = match file.preset {
+            Some(version) => ResourceConfig::preset(version),
+            None => ResourceConfig::new(),
+        };
+        for (item_id, entry) in file.resources.iter() {
+            if let Some(limit) = entry.limit {
+                config.set_limit(item_id, limit);
+            } else if !config.contains(item_id) {
+                config.set_limit(item_id, f64::INFINITY);
+            }
+            if let Some(weight) = entry.weight {
+                config.set_weight(item_id, weight);
+            }
+        }
+        config.set_scarcity_weights();
+        return config;
+    }
+
+    fn get_scarcity_weight(&self, limit: f64) -> f64 {
+        if !limit.is_finite() || limit <= 0.0 {
+            return MIN_WEIGHT;
+        }
+        return (SCARCITY_SCALE * self.scarcity_limit / limit).max(MIN_WEIGHT);
+    }
+
+    //Weights scale inversely with availability, so the most plentiful resource costs SCARCITY_SCALE.
+    //Every derived weight is recomputed here rather than on every read, so editing a limit later only changes that limit
+    pub fn set_scarcity_weights(&mut self) -> () {
+        self.scarcity_limit = self.resources.values()
+            .map(|resource| resource.limit)
+            .filter(|limit| limit.is_finite())
+            .fold(0.0, f64::max);
+        let limits: Vec<(String, f64)> = self.resources.iter().map(|(item_id, resource)| (item_id.clone(), resource.limit)).collect::<Vec<(String, f64)>>();
+        for (item_id, limit) in limits {
+            let scarcity_weight: f64 = self.get_scarcity_weight(limit);
+            self.resources.get_mut(&item_id).unwrap().scarcity_weight = scarcity_weight;
+        }
+    }
+
+    //Resources added after the weights are set get theirs from the same reference limit
+    pub fn set_limit(&mut self, item_id: &str, limit: f64) -> () {
+        match self.resources.get_mut(item_id) {
+            Some(resource) => resource.limit = limit,
+            None => {
+                let scarcity_weight: f64 = self.get_scarcity_weight(limit);
+                self.resources.insert(item_id.to_string(), Resource { limit, weight: None, scarcity_weight });
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn set_weight(&mut self, item_id: &str, weight: f64) -> () {
+        match self.resources.get_mut(item_id) {
+            Some(resource) => resource.weight = Some(weight),
+            None => panic!("\"{}\" has no resource limit", item_id),
+        }
+    }
+
+    pub fn remove(&mut self, item_id: &str) -> () {
+        self.resources.remove(item_id);
+    }
+
+    pub fn contains(&self, item_id: &str) -> bool {
+        return self.resources.contains_key(item_id);
+    }
+
+    pub fn item_ids(&self) -> Vec<String> {
+        return self.resources.keys().cloned().collect::<Vec<String>>();
+    }
+
+    pub fn get_limit(&self, item_id: &str) -> f64 {
+        return self.resources.get(item_id).unwrap().limit;
+    }
+
+    pub fn get_weight(&self, item_id: &str) -> f64 {
+        let resource: &Resource = self.resources.get(item_id).unwrap();
+        return resource.weight.unwrap_or(resource.scarcity_weight);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_weight(config: &ResourceConfig, item_id: &str, weight: f64) -> () {
+        assert!((config.get_weight(item_id) - weight).abs() < 1e-9, "{} has weight {}, expected {}", item_id, config.get_weight(item_id), weight);
+    }
+
+    fn load_file(name: &str, contents: &str) -> ResourceConfig {
+        let path: std::path::PathBuf = std::env::temp_dir().join(format!("satisfactory_{}_{}.json", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        let config: ResourceConfig = ResourceConfig::from_file(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        return config;
+    }
+
+    #[test]
+    fn weights_presets_by_scarcity() {
+        let config: ResourceConfig = ResourceConfig::preset(GameVersion::Update8);
+        assert_weight(&config, "Desc_OreIron_C", SCARCITY_SCALE);
+        assert_weight(&config, "Desc_OreUranium_C", SCARCITY_SCALE * 70380.0 / 2100.0);
+        assert_weight(&config, "Desc_Water_C", MIN_WEIGHT);
+        assert_eq!(config.get_limit("Desc_Water_C"), f64::INFINITY);
+        let config: ResourceConfig = ResourceConfig::preset(GameVersion::Release1);
+        assert_weight(&config, "Desc_OreIron_C", SCARCITY_SCALE);
+        assert_weight(&config, "Desc_SAM_C", SCARCITY_SCALE * 92100.0 / 10200.0);
+    }
+
+    #[test]
+    fn reweights_preset_resources_changed_by_a_file() {
+        let config: ResourceConfig = load_file("lowered", r#"{
+            "preset": "Update8",
+            "resources": {
+                "Desc_OreIron_C": { "limit": 10000 },
+                "Desc_Coal_C": { "weight": 3 },
+                "Desc_SAM_C": { "limit": 5000 }
+            }
+        }"#);
+        assert_eq!(config.get_limit("Desc_OreIron_C"), 10000.0);
+        assert_weight(&config, "Desc_Stone_C", SCARCITY_SCALE);
+        assert_weight(&config, "Desc_OreIron_C", SCARCITY_SCALE * 52860.0 / 10000.0);
+        assert_weight(&config, "Desc_OreUranium_C", SCARCITY_SCALE * 52860.0 / 2100.0);
+        assert_weight(&config, "Desc_SAM_C", SCARCITY_SCALE * 52860.0 / 5000.0);
+        assert_weight(&config, "Desc_Coal_C", 3.0);
+        assert_eq!(config.get_limit("Desc_Coal_C"), 30120.0);
+        let config: ResourceConfig = load_file("raised", r#"{
+            "preset": "Update8",
+            "resources": { "Desc_OreIron_C": { "limit": 100000, "weight": 0.5 } }
+        }"#);
+        assert_weight(&config, "Desc_OreIron_C", 0.5);
+        assert_weight(&config, "Desc_Stone_C", SCARCITY_SCALE * 100000.0 / 52860.0);
+    }
+
+    #[test]
+    fn loads_a_file_without_a_preset() {
+        let config: ResourceConfig = load_file("bare", r#"{ "resources": { "Desc_OreIron_C": { "limit": 600 }, "Desc_Water_C": {} } }"#);
+        assert_eq!(config.item_ids().len(), 2);
+        assert_weight(&config, "Desc_OreIron_C", SCARCITY_SCALE);
+        assert_eq!(config.get_limit("Desc_Water_C"), f64::INFINITY);
+        assert_weight(&config, "Desc_Water_C", MIN_WEIGHT);
+    }
+
+    #[test]
+    fn keeps_weights_when_limits_change_after_loading() {
+        let mut config: ResourceConfig = ResourceConfig::preset(GameVersion::Update8);
+        config.set_limit("Desc_OreIron_C", 100.0);
+        assert_weight(&config, "Desc_OreIron_C", SCARCITY_SCALE);
+        config.set_limit("Desc_SAM_C", 7038.0);
+        assert_weight(&config, "Desc_SAM_C", SCARCITY_SCALE * 10.0);
+    }
+}
@@ -1,13 +1,131 @@
 use std::collections::{HashMap, HashSet};
+use std::fs;
 
-use minilp::{ComparisonOp, LinearExpr, OptimizationDirection, Problem, Solution, Variable};
+use std::rc::Rc;
+use num_rational::BigRational;
 
-use crate::json::{GameData, ItemQuantity, Recipe};
+use crate::backend::{ComparisonOp, LinearProgram, LpBackend, LpSolution, MinilpBackend, ModelStats, SolveError, Variable};
+use crate::exact;
+use crate::export::{self, ModelFormat};
+use crate::graph;
+use crate::json::{GameData, Generator, ItemQuantity, Recipe};
+use crate::numeric;
+use crate::resources::{GameVersion, ResourceConfig};
 
-#[derive(Debug)]
-pub struct Resource {
-    limit: f64,
-    weight: f64,
+const WATER_ID: &str = "Desc_Water_C";
+const WATER_EXTRACTOR_ID: &str = "Desc_WaterPump_C";
+const WATER_EXTRACTOR_RATE: f64 = 120.0;
+const CLOCK_TOLERANCE: f64 = 0.000001;
+const MACHINE_COST: f64 = 0.25;
+const VERIFY_TOLERANCE: f64 = 0.0001;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClockPreference {
+    FewerMachines,
+    LowerPower,
+}
+
+//Under LowerPower one more machine is only worth building if it saves at least machine_cost
+//of one machine's power at 100% clock
+#[derive(Debug, Clone)]
+pub struct ClockPolicy {
+    pub max_clock: f64,
+    pub min_clock: f64,
+    pub shard_budget: u32,
+    pub preference: ClockPreference,
+    pub machine_cost: f64,
+}
+
+impl ClockPolicy {
+    pub fn new(max_clock: f64, min_clock: f64, shard_budget: u32, preference: ClockPreference) -> ClockPolicy {
+        if !(1.0..=2.5).contains(&max_clock) || !(0.01..=1.0).contains(&min_clock) {
+            panic!("Clock range {}-{} is not allowed", min_clock, max_clock);
+        }
+        return ClockPolicy { max_clock, min_clock, shard_budget, preference, machine_cost: MACHINE_COST };
+    }
+
+    fn get_clock_levels(&self) -> Vec<f64> {
+        let mut levels: Vec<f64> = vec![1.0];
+        for shards in 1..=3 {
+            let clock: f64 = 1.0 + 0.5 * (shards as f64);
+            if clock > self.max_clock + CLOCK_TOLERANCE {
+                break;
+            }
+            levels.push(clock);
+        }
+        return levels;
+    }
+
+    fn get_max_shards(&self) -> u32 {
+        return (self.get_clock_levels().len() - 1) as u32;
+    }
+
+    //Only FewerMachines spends shards, and only while the budget has some
+    fn uses_shards(&self) -> bool {
+        return self.preference == ClockPreference::FewerMachines && self.shard_budget > 0 && self.get_max_shards() > 0;
+    }
+}
+
+//Full machines are grouped by clock, fastest first, and the remainder machine takes whatever load they leave
+#[derive(Debug, Clone)]
+struct ClockSetting {
+    full_machines: Vec<(u32, f64)>,
+    remainder_clock: Option<f64>,
+}
+
+impl ClockSetting {
+    fn get_clocks(&self) -> Vec<(u32, f64)> {
+        let mut clocks: Vec<(u32, f64)> = self.full_machines.clone();
+        clocks.extend(self.remainder_clock.map(|clock| (1, clock)));
+        return clocks;
+    }
+
+    fn get_buildings(&self) -> u32 {
+        return self.get_clocks().iter().map(|&(machines, _)| machines).sum();
+    }
+
+    fn get_shards(&self) -> u32 {
+        return self.get_clocks().iter().map(|&(machines, clock)| machines * get_clock_shards(clock)).sum();
+    }
+
+    fn get_average_clock(&self) -> f64 {
+        let buildings: u32 = self.get_buildings();
+        if buildings == 0 {
+            return 0.0;
+        }
+        return self.get_clocks().iter().fold(0.0, |acc, &(machines, clock)| acc + machines as f64 * clock) / buildings as f64;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(dead_code)]
+pub enum ByproductPolicy {
+    Sink,
+    Store,
+    Forbid,
+    Output,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
+pub enum Target {
+    Exact(f64),
+    AtLeast(f64),
+    AtMost(f64),
+    Range(f64, f64),
+}
+
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub limit: f64,
+    pub weight: f64,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone)]
+struct GeneratorNode {
+    generator: String,
+    fuel: String,
 }
 
 #[derive(Debug, Clone)]
@@ -27,135 +145,332 @@ impl Link {
     }
 }
 
-#[derive(Debug)]
+//Links are stored by source, with indices of (source, position) by destination and item and of
+//positions by source and item, so no lookup has to scan every link
+#[derive(Debug, Clone)]
 struct LinkSet {
     links: HashMap<String, Vec<Link>>,
+    incoming: HashMap<String, HashMap<String, Vec<(String, usize)>>>,
+    outgoing: HashMap<String, HashMap<String, Vec<usize>>>,
 }
 
 impl LinkSet {
     fn new() -> LinkSet {
         return LinkSet {
             links: HashMap::new(),
+            incoming: HashMap::new(),
+            outgoing: HashMap::new(),
         }
     }
 
-    fn add_simple_variable(&mut self, source: &str, destination: &str, item: &str, problem: &mut Problem) -> () {
-        let link: Link = Link::new(item, destination, problem.add_var(1.0, (0.0, f64::MAX)));
-        if self.links.contains_key(source) {
-            self.links.get_mut(source).unwrap().push(link);
-        } else {
-            self.links.insert(source.to_string(), vec![link]);
-        }
+    fn add_link(&mut self, source: &str, link: Link) -> () {
+        let source_links: &mut Vec<Link> = self.links.entry(source.to_string()).or_default();
+        let position: usize = source_links.len();
+        self.incoming.entry(link.destination.clone()).or_default().entry(link.item.clone()).or_default().push((source.to_string(), position));
+        self.outgoing.entry(source.to_string()).or_default().entry(link.item.clone()).or_default().push(position);
+        source_links.push(link);
     }
 
-    fn add_weighted_variable(&mut self, source: &str, destination: &str, item: &str, weight: f64, problem: &mut Problem) -> () {
-        let link: Link = Link::new(item, destination, problem.add_var(weight, (0.0, f64::MAX)));
-        if self.links.contains_key(source) {
-            self.links.get_mut(source).unwrap().push(link);
-        } else {
-            self.links.insert(source.to_string(), vec![link]);
-        }
+    fn add_simple_variable(&mut self, source: &str, destination: &str, item: &str, model: &mut LinearProgram) -> () {
+        let link: Link = Link::new(item, destination, model.add_var(&get_link_name(source, item, destination), 1.0, (0.0, f64::INFINITY)));
+        self.add_link(source, link);
     }
 
-    fn add_resource_variable(&mut self, resource: &Resource, destination: &str, item: &str, problem: &mut Problem) -> () {
-        let link: Link = Link::new(item, destination, problem.add_var(resource.weight, (0.0, resource.limit)));
-        if self.links.contains_key(item) {
-            self.links.get_mut(item).unwrap().push(link);
-        } else {
-            self.links.insert(item.to_string(), vec![link]);
-        }
+    fn add_weighted_variable(&mut self, source: &str, destination: &str, item: &str, weight: f64, model: &mut LinearProgram) -> () {
+        let link: Link = Link::new(item, destination, model.add_var(&get_link_name(source, item, destination), weight, (0.0, f64::INFINITY)));
+        self.add_link(source, link);
+    }
+
+    fn add_resource_variable(&mut self, weight: f64, destination: &str, item: &str, model: &mut LinearProgram) -> () {
+        let link: Link = Link::new(item, destination, model.add_var(&get_link_name(item, item, destination), weight, (0.0, f64::INFINITY)));
+        self.add_link(item, link);
+    }
+
+    fn get_destinations(&self) -> impl Iterator<Item = &String> {
+        return self.incoming.keys();
     }
 
     fn get_incoming_for_item(&self, destination: &str, item: &str) -> Vec<&Link> {
-        let mut incoming: Vec<&Link> = Vec::new();
-        for links in self.links.values() {
-            for link in links.iter() {
-                if (link.destination == destination) && (link.item == item) {
-                    incoming.push(&link);
-                }
-            }
-        }
-        return incoming;
+        let positions: Option<&Vec<(String, usize)>> = self.incoming.get(destination).and_then(|items| items.get(item));
+        return positions.into_iter().flatten().map(|(source, position)| &self.links[source][*position]).collect::<Vec<&Link>>();
     }
 
     fn get_outgoing_for_item(&self, source: &str, item: &str) -> Vec<&Link> {
-        let mut outgoing: Vec<&Link> = Vec::new();
-        for link in self.links.get(source).unwrap().iter() {
-            if link.item == item {
-                outgoing.push(&link);
-            }
-        }
-        return outgoing;
+        let positions: Option<&Vec<usize>> = self.outgoing.get(source).and_then(|items| items.get(item));
+        return positions.into_iter().flatten().map(|&position| &self.links[source][position]).collect::<Vec<&Link>>();
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Solver {
     data: GameData,
-    resources: HashMap<String, Resource>,
+    resources: ResourceConfig,
+    imports: HashMap<String, Import>,
     disallowed_recipes: HashSet<String>,
     preserved_recipes: HashSet<String>,
+    recipe_weights: HashMap<String, f64>,
+    building_weights: HashMap<String, f64>,
     byproduct_coefficient: f64,
-    targets: HashMap<String, f64>,
+    byproduct_policies: HashMap<String, ByproductPolicy>,
+    targets: HashMap<String, Target>,
+    target_values: HashMap<String, f64>,
+    ratios: Vec<Vec<(String, f64)>>,
+    self_powered: bool,
+    integer_buildings: bool,
+    building_coefficient: f64,
+    snap_rates: bool,
+    prune_epsilon: f64,
+    clock_policy: ClockPolicy,
 
-    problem: Problem,
+    model_built: bool,
+    excluded_recipes: HashSet<String>,
+    expanded_recipes: HashSet<String>,
+    backend: Rc<dyn LpBackend>,
+    model: LinearProgram,
     links: LinkSet,
+    generators: HashMap<String, GeneratorNode>,
+    building_variables: HashMap<String, Variable>,
+    shard_variables: HashMap<String, Variable>,
 }
 
 impl Solver {
     pub fn new(data: GameData, targets: HashMap<String, f64>) -> Solver {
         return Solver {
             data,
-            resources: HashMap::from([
-                ("Desc_Coal_C".to_string(),         Resource { limit: 30120.0,  weight: 10.0    } ),
-                ("Desc_LiquidOil_C".to_string(),    Resource { limit: 11700.0,  weight: 25.0    } ),
-                ("Desc_NitrogenGas_C".to_string(),  Resource { limit: 12000.0,  weight: 50.0    } ),
-                ("Desc_OreBauxite_C".to_string(),   Resource { limit: 9780.0,   weight: 50.0    } ),
-                ("Desc_OreCopper_C".to_string(),    Resource { limit: 28860.0,  weight: 10.0    } ),
-                ("Desc_OreGold_C".to_string(),      Resource { limit: 11040.0,  weight: 25.0    } ),
-                ("Desc_OreIron_C".to_string(),      Resource { limit: 70380.0,  weight: 10.0    } ),
-                ("Desc_OreUranium_C".to_string(),   Resource { limit: 2100.0,   weight: 100.0   } ),
-                ("Desc_RawQuartz_C".to_string(),    Resource { limit: 10500.0,  weight: 50.0    } ),
-                ("Desc_Stone_C".to_string(),        Resource { limit: 52860.0,  weight: 10.0    } ),
-                ("Desc_Sulfur_C".to_string(),       Resource { limit: 6840.0,   weight: 50.0    } ),
-                ("Desc_Water_C".to_string(),        Resource { limit: f64::MAX, weight: 1.0     } ),
-            ]),
+            resources: ResourceConfig::preset(GameVersion::Update8),
+            imports: HashMap::new(),
             disallowed_recipes: HashSet::new(),
             preserved_recipes: HashSet::new(),
+            recipe_weights: HashMap::new(),
+            building_weights: HashMap::new(),
             byproduct_coefficient: 1000.0,
-            targets,
+            byproduct_policies: HashMap::new(),
+            targets: targets.into_iter().map(|(item_id, rate)| (item_id, Target::Exact(rate))).collect::<HashMap<String, Target>>(),
+            target_values: HashMap::new(),
+            ratios: Vec::new(),
+            self_powered: false,
+            integer_buildings: false,
+            building_coefficient: 100.0,
+            snap_rates: true,
+            prune_epsilon: 0.00001,
+            clock_policy: ClockPolicy::new(1.0, 1.0, 0, ClockPreference::FewerMachines),
 
-            problem: Problem::new(OptimizationDirection::Minimize),
+            model_built: false,
+            excluded_recipes: HashSet::new(),
+            expanded_recipes: HashSet::new(),
+            backend: Rc::new(MinilpBackend),
+            model: LinearProgram::new(),
             links: LinkSet::new(),
+            generators: HashMap::new(),
+            building_variables: HashMap::new(),
+            shard_variables: HashMap::new(),
         };
     }
 
+    //Goes through set_resource_limit so an existing resource is treated the same either way
+    #[allow(dead_code)]
     pub fn add_resource(&mut self, resource: &str, amount: f64) -> () {
-        self.resources.insert(resource.to_string(), Resource { limit: amount, weight: 0.0 });
+        let exists: bool = self.resources.contains(resource);
+        self.set_resource_limit(resource, amount);
+        if !exists {
+            self.resources.set_weight(resource, 0.0);
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn set_resource_config(&mut self, resources: ResourceConfig) -> () {
+        self.resources = resources;
+        self.model_built = false;
+    }
+
+    //Limits only bound the model; a new resource adds variables, so it needs a rebuild
+    pub fn set_resource_limit(&mut self, resource: &str, limit: f64) -> () {
+        if !self.resources.contains(resource) {
+            self.model_built = false;
+        }
+        self.resources.set_limit(resource, limit);
+    }
+
+    pub fn remove_resource(&mut self, resource: &str) -> () {
+        self.resources.remove(resource);
+        self.model_built = false;
+    }
+
+    #[allow(dead_code)]
+    pub fn set_resource_weight(&mut self, resource: &str, weight: f64) -> () {
+        self.resources.set_weight(resource, weight);
+        self.model_built = false;
+    }
+
+    pub fn set_target(&mut self, item_id: &str, target: Target) -> () {
+        if self.targets.insert(item_id.to_string(), target).is_none() {
+            self.model_built = false;
+        }
+    }
+
+    pub fn remove_target(&mut self, item_id: &str) -> () {
+        self.targets.remove(item_id);
+        self.ratios.retain(|ratio| ratio.iter().all(|(ratio_item_id, _)| ratio_item_id != item_id));
+        self.model_built = false;
+    }
+
+    #[allow(dead_code)]
+    pub fn set_target_value(&mut self, item_id: &str, value: f64) -> () {
+        self.target_values.insert(item_id.to_string(), value);
+        self.model_built = false;
+    }
+
+    #[allow(dead_code)]
+    pub fn clear_ratios(&mut self) -> () {
+        self.ratios.clear();
+    }
+
+    #[allow(dead_code)]
+    pub fn add_ratio(&mut self, ratio: &[(&str, f64)]) -> () {
+        for &(item_id, amount) in ratio.iter() {
+            if !self.targets.contains_key(item_id) {
+                panic!("\"{}\" is not a target", item_id);
+            }
+            if amount <= 0.0 {
+                panic!("Ratio amount for \"{}\" must be positive", item_id);
+            }
+        }
+        self.ratios.push(ratio.iter().map(|&(item_id, amount)| (item_id.to_string(), amount)).collect::<Vec<(String, f64)>>());
+    }
+
+    #[allow(dead_code)]
+    pub fn add_import(&mut self, item_id: &str, limit: f64, weight: f64, required: bool) -> () {
+        if !self.data.items.contains_key(item_id) {
+            panic!("\"{}\" is not an item", item_id);
+        }
+        let previous: Option<Import> = self.imports.insert(item_id.to_string(), Import { limit, weight, required });
+        if previous.is_none_or(|import| import.weight != weight) {
+            self.model_built = false;
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn set_byproduct_policy(&mut self, item_id: &str, policy: ByproductPolicy) -> () {
+        if !self.data.items.contains_key(item_id) {
+            panic!("\"{}\" is not an item", item_id);
+        }
+        if !self.data.is_sinkable(item_id) && (policy == ByproductPolicy::Sink || policy == ByproductPolicy::Output) {
+            panic!("\"{}\" cannot be sunk and must be consumed or stored", item_id);
+        }
+        self.byproduct_policies.insert(item_id.to_string(), policy);
+        self.model_built = false;
     }
 
     pub fn remove_recipe(&mut self, recipe_id: &str) -> () {
         self.disallowed_recipes.insert(recipe_id.to_string());
+        self.model_built = false;
+    }
+
+    pub fn allow_recipe(&mut self, recipe_id: &str) -> () {
+        self.disallowed_recipes.remove(recipe_id);
+        self.model_built = false;
+    }
+
+    pub fn is_recipe_allowed(&self, recipe_id: &str) -> bool {
+        return !self.disallowed_recipes.contains(recipe_id);
+    }
+
+    //Loops the allowed recipes could form, whether or not a solved plan ends up using them
+    #[allow(dead_code)]
+    pub fn get_recipe_cycles(&self) -> Vec<Vec<String>> {
+        return self.data.get_recipe_cycles(&self.disallowed_recipes);
+    }
+
+    pub fn get_data(&self) -> &GameData {
+        return &self.data;
+    }
+
+    pub fn get_resources(&self) -> &ResourceConfig {
+        return &self.resources;
+    }
+
+    pub fn get_targets(&self) -> &HashMap<String, Target> {
+        return &self.targets;
     }
 
     pub fn preserve_recipe(&mut self, recipe_id: &str) -> () {
         self.preserved_recipes.insert(recipe_id.to_string());
     }
 
+    #[allow(dead_code)]
+    pub fn set_recipe_weight(&mut self, recipe_id: &str, weight: f64) -> () {
+        if !self.data.recipes.contains_key(recipe_id) {
+            panic!("\"{}\" is not a recipe", recipe_id);
+        }
+        self.recipe_weights.insert(recipe_id.to_string(), weight);
+        self.model_built = false;
+    }
+
+    #[allow(dead_code)]
+    pub fn set_building_weight(&mut self, building_id: &str, weight: f64) -> () {
+        if !self.data.buildings.contains_key(building_id) {
+            panic!("\"{}\" is not a building", building_id);
+        }
+        self.building_weights.insert(building_id.to_string(), weight);
+        self.model_built = false;
+    }
+
     pub fn remove_alternates(&mut self) -> () {
         for recipe in self.data.recipes.values() {
             if recipe.alternate && !self.preserved_recipes.contains(&recipe.class_name) {
                 self.disallowed_recipes.insert(recipe.class_name.clone());
             }
         }
+        self.model_built = false;
+    }
+
+    #[allow(dead_code)]
+    pub fn set_self_powered(&mut self, self_powered: bool) -> () {
+        self.self_powered = self_powered;
+        self.model_built = false;
+    }
+
+    #[allow(dead_code)]
+    pub fn set_integer_buildings(&mut self, integer_buildings: bool) -> () {
+        self.integer_buildings = integer_buildings;
+        self.model_built = false;
+    }
+
+    #[allow(dead_code)]
+    pub fn set_building_coefficient(&mut self, building_coefficient: f64) -> () {
+        self.building_coefficient = building_coefficient;
+        self.model_built = false;
+    }
+
+    #[allow(dead_code)]
+    pub fn set_snap_rates(&mut self, snap_rates: bool) -> () {
+        self.snap_rates = snap_rates;
+    }
+
+    #[allow(dead_code)]
+    pub fn set_backend(&mut self, backend: Rc<dyn LpBackend>) -> () {
+        self.backend = backend;
+    }
+
+    #[allow(dead_code)]
+    pub fn set_prune_epsilon(&mut self, prune_epsilon: f64) -> () {
+        self.prune_epsilon = prune_epsilon;
+    }
+
+    #[allow(dead_code)]
+    pub fn set_clock_policy(&mut self, clock_policy: ClockPolicy) -> () {
+        if self.integer_buildings || self.self_powered {
+            self.model_built = false;
+        }
+        self.clock_policy = clock_policy;
     }
 
     fn is_feasible(&self, recipe_id: &str) -> bool {
         let mut resource_provided: bool;
         let mut no_recipes_exist: bool;
         for ingredient in self.data.get_ingredients(recipe_id) {
-            resource_provided = self.resources.contains_key(&ingredient);
-            no_recipes_exist = self.data.get_item_creators(&ingredient, &self.disallowed_recipes).is_empty();
+            resource_provided = self.resources.contains(&ingredient) || self.imports.contains_key(&ingredient);
+            no_recipes_exist = self.data.get_item_creators(&ingredient, &self.excluded_recipes).is_empty();
             if !resource_provided && no_recipes_exist  {
                 return false;
             }
@@ -165,203 +480,1176 @@ impl Solver {
 
     fn trim_parents(&mut self, recipe_id: &str) -> () {
         for product in self.data.get_products(recipe_id) {
-            for parent_id in self.data.get_item_users(&product, &self.disallowed_recipes) {
+            for parent_id in self.data.get_item_users(&product, &self.excluded_recipes) {
                 if !self.is_feasible(&parent_id) {
-                    self.disallowed_recipes.insert(parent_id.clone());
+                    self.excluded_recipes.insert(parent_id.clone());
                     self.trim_parents(&parent_id);
                 }
             }
         }
     }
 
+    fn is_available(&self, item_id: &str) -> bool {
+        return self.resources.contains(item_id) || self.imports.contains_key(item_id) || !self.data.get_item_creators(item_id, &self.excluded_recipes).is_empty();
+    }
+
+    fn add_ingredient_variables(&mut self, node_id: &str, ingredient: &str) -> () {
+        if self.resources.contains(ingredient) {
+            self.links.add_resource_variable(self.resources.get_weight(ingredient), node_id, ingredient, &mut self.model);
+        }
+        if let Some(import) = self.imports.get(ingredient) {
+            self.links.add_weighted_variable(&get_import_node_id(ingredient), node_id, ingredient, import.weight, &mut self.model);
+        }
+        for child_recipe_id in self.data.get_item_creators(ingredient, &self.excluded_recipes) {
+            self.links.add_simple_variable(&child_recipe_id, node_id, ingredient, &mut self.model);
+            self.add_variables(&child_recipe_id);
+        }
+    }
+
+    //Each recipe's ingredients are linked once, which stops recursion around recipe loops
+    //and keeps a recipe reached from several places from getting duplicate input links
     fn add_variables(&mut self, recipe_id: &str) -> () {
+        if !self.expanded_recipes.insert(recipe_id.to_string()) {
+            return;
+        }
         for ingredient in self.data.get_ingredients(recipe_id) {
-            if self.resources.contains_key(&ingredient) {
-                let resource: &Resource = self.resources.get(&ingredient).unwrap();
-                self.links.add_resource_variable(resource, recipe_id, &ingredient, &mut self.problem);
-            }
-            for child_recipe_id in self.data.get_item_creators(&ingredient, &self.disallowed_recipes) {
-                let exists: bool = self.links.links.contains_key(&child_recipe_id);
-                self.links.add_simple_variable(&child_recipe_id, recipe_id, &ingredient, &mut self.problem);
-                if !exists {
-                    self.add_variables(&child_recipe_id);
+            self.add_ingredient_variables(recipe_id, &ingredient);
+        }
+    }
+
+    fn add_generators(&mut self) -> () {
+        let generators: Vec<Generator> = self.data.generators.values().cloned().collect::<Vec<Generator>>();
+        for generator in generators {
+            if generator.water_to_power_ratio > 0.0 && !self.is_available(WATER_ID) {
+                continue;
+            }
+            for fuel in generator.fuel.iter() {
+                if self.data.get_item(fuel).energy_value <= 0.0 || !self.is_available(fuel) {
+                    continue;
+                }
+                let node_id: String = format!("Generator_{}_{}", generator.class_name, fuel);
+                self.generators.insert(node_id.clone(), GeneratorNode { generator: generator.class_name.clone(), fuel: fuel.clone() });
+                self.add_ingredient_variables(&node_id, fuel);
+                if generator.water_to_power_ratio > 0.0 {
+                    self.add_ingredient_variables(&node_id, WATER_ID);
                 }
             }
         }
     }
 
-    fn get_underclock(&self, recipe_id: &str, item_id: &str, rate: f64) -> f64 {
-        if recipe_id.starts_with("Desc_") {
-            return rate;
+    fn get_recipe_nodes(&self) -> Vec<String> {
+        let recipe_ids: HashSet<&String> = self.links.links.keys().chain(self.links.get_destinations()).filter(|&node_id| self.data.recipes.contains_key(node_id)).collect::<HashSet<&String>>();
+        return recipe_ids.into_iter().cloned().collect::<Vec<String>>();
+    }
+
+    fn get_byproduct_policy(&self, item_id: &str) -> ByproductPolicy {
+        if let Some(&policy) = self.byproduct_policies.get(item_id) {
+            return policy;
         }
-        let recipe: &Recipe = self.data.get_recipe(recipe_id);
-        let mut amount: f64 = 0.0;
-        for product in recipe.products.iter() {
-            if product.item == item_id {
-                amount = product.amount;
+        if !self.data.is_sinkable(item_id) {
+            return ByproductPolicy::Forbid;
+        }
+        return ByproductPolicy::Output;
+    }
+
+    fn add_byproduct_variables(&mut self, node_id: &str, product: &str) -> () {
+        match self.get_byproduct_policy(product) {
+            ByproductPolicy::Sink => self.links.add_weighted_variable(node_id, &format!("Sink_{}", product), product, self.byproduct_coefficient, &mut self.model),
+            ByproductPolicy::Store => self.links.add_weighted_variable(node_id, &format!("Store_{}", product), product, self.byproduct_coefficient, &mut self.model),
+            ByproductPolicy::Output => self.links.add_weighted_variable(node_id, product, product, self.byproduct_coefficient, &mut self.model),
+            ByproductPolicy::Forbid => {
+                for user_id in self.data.get_item_users(product, &self.excluded_recipes) {
+                    self.links.add_simple_variable(node_id, &user_id, product, &mut self.model);
+                    self.add_variables(&user_id);
+                }
+                let burners: Vec<String> = self.generators.iter().filter(|&(_, generator_node)| generator_node.fuel == product).map(|(generator_id, _)| generator_id.clone()).collect::<Vec<String>>();
+                for generator_id in burners {
+                    self.links.add_simple_variable(node_id, &generator_id, product, &mut self.model);
+                }
             }
         }
-        return rate / ((rate * recipe.time) / (60.0 * amount)).ceil();
     }
 
-    pub fn solve(mut self) -> Factory {
+    fn get_import_item<'a>(&self, node_id: &'a str) -> Option<&'a str> {
+        return node_id.strip_prefix("Import_").filter(|&item_id| self.imports.contains_key(item_id));
+    }
+
+    fn get_node_name(&self, node_id: &str) -> String {
+        if let Some(generator_node) = self.generators.get(node_id) {
+            return format!("{} ({})", self.data.get_building_name(&generator_node.generator), self.data.get_item_name(&generator_node.fuel));
+        }
+        if let Some(item_id) = self.get_import_item(node_id) {
+            return format!("{} (import)", self.data.get_item_name(item_id));
+        }
+        if let Some(item_id) = node_id.strip_prefix("Sink_") {
+            return format!("{} (AWESOME Sink)", self.data.get_item_name(item_id));
+        }
+        if let Some(item_id) = node_id.strip_prefix("Store_") {
+            return format!("{} (storage)", self.data.get_item_name(item_id));
+        }
+        if node_id.starts_with("Desc_") {
+            return self.data.get_item_name(node_id);
+        }
+        return self.data.get_recipe_name(node_id);
+    }
+
+    //Item nodes are shared by resources, targets and byproduct outputs, so the order of checks matters
+    fn get_node_type(&self, node_id: &str) -> NodeType {
+        if self.get_import_item(node_id).is_some() {
+            return NodeType::Import;
+        }
+        if self.generators.contains_key(node_id) {
+            return NodeType::Generator;
+        }
+        if self.data.recipes.contains_key(node_id) {
+            return NodeType::Production;
+        }
+        if node_id.starts_with("Sink_") {
+            return NodeType::Sink;
+        }
+        if node_id.starts_with("Store_") {
+            return NodeType::Storage;
+        }
+        if self.targets.contains_key(node_id) {
+            return NodeType::Target;
+        }
+        if self.resources.contains(node_id) && self.links.links.contains_key(node_id) {
+            if self.get_extractor_power(node_id) > 0.0 {
+                return NodeType::Extractor;
+            }
+            return NodeType::RawResource;
+        }
+        return NodeType::Byproduct;
+    }
+
+    //Per-building rates are at 100% clock, generators use their fuel rate at full output
+    fn set_node_building(&self, node: &mut Node) -> () {
+        if let Some(generator_node) = self.generators.get(&node.id) {
+            let generator: &Generator = self.data.generators.get(&generator_node.generator).unwrap();
+            let fuel_rate: f64 = generator.power_production * 60.0 / self.data.get_item(&generator_node.fuel).energy_value;
+            node.building_inputs.insert(generator_node.fuel.clone(), fuel_rate);
+            if generator.water_to_power_ratio > 0.0 {
+                node.building_inputs.insert(WATER_ID.to_string(), fuel_rate * self.data.get_item(&generator_node.fuel).energy_value * generator.water_to_power_ratio / 1000.0);
+            }
+            if let Some((waste, amount)) = get_fuel_waste(&generator_node.fuel) {
+                node.building_outputs.insert(waste.to_string(), fuel_rate * amount);
+            }
+            node.building_name = self.data.buildings.get(&generator.class_name).map(|building| building.name.clone());
+            node.building_id = Some(generator.class_name.clone());
+            return;
+        }
+        let recipe: &Recipe = match self.data.recipes.get(&node.id) {
+            Some(recipe) => recipe,
+            None => return,
+        };
+        for ItemQuantity { item, .. } in recipe.ingredients.iter() {
+            node.building_inputs.insert(item.clone(), self.data.get_ingredient_rate(&node.id, item));
+        }
+        for ItemQuantity { item, .. } in recipe.products.iter() {
+            node.building_outputs.insert(item.clone(), self.data.get_product_rate(&node.id, item));
+        }
+        node.recipe_id = Some(node.id.clone());
+        node.building_id = recipe.produced_in.first().cloned();
+        node.building_name = node.building_id.as_ref().and_then(|building_id| self.data.buildings.get(building_id)).map(|building| building.name.clone());
+    }
+
+    fn get_node_products(&self, node_id: &str) -> Vec<String> {
+        if let Some(generator_node) = self.generators.get(node_id) {
+            return match get_fuel_waste(&generator_node.fuel) {
+                Some((waste, _)) => vec![waste.to_string()],
+                None => Vec::new(),
+            };
+        }
+        return self.data.get_products(node_id);
+    }
+
+    fn get_extractor_power(&self, resource: &str) -> f64 {
+        if resource == WATER_ID {
+            return self.data.get_building(WATER_EXTRACTOR_ID).metadata.power_consumption / WATER_EXTRACTOR_RATE;
+        }
+        if !self.data.resources.contains_key(resource) {
+            return 0.0;
+        }
+        let divisor: f64 = if self.data.get_item(resource).liquid { 1000.0 } else { 1.0 };
+        let mut best: Option<(f64, f64)> = None;
+        for miner in self.data.miners.values().filter(|&miner| miner.allowed_resources.iter().any(|allowed| allowed == resource)) {
+            let rate: f64 = miner.items_per_cycle * 60.0 / (miner.extract_cycle_time * divisor);
+            if best.is_none_or(|(best_rate, _)| rate > best_rate) {
+                best = Some((rate, self.data.get_building(&miner.class_name).metadata.power_consumption));
+            }
+        }
+        return match best {
+            Some((rate, power)) => power / rate,
+            None => 0.0,
+        };
+    }
+
+    fn get_extraction_power_terms(&self) -> Vec<(Variable, f64)> {
+        let mut terms: Vec<(Variable, f64)> = Vec::new();
+        for (node_id, links) in self.links.links.iter().filter(|&(node_id, _)| node_id.starts_with("Desc_")) {
+            let power: f64 = self.get_extractor_power(node_id);
+            terms.extend(links.iter().map(|l| (l.variable, power)));
+        }
+        return terms;
+    }
+
+    fn get_power_consumption_terms(&self) -> Vec<(Variable, f64)> {
+        let mut terms: Vec<(Variable, f64)> = self.get_extraction_power_terms();
+        for (node_id, links) in self.links.links.iter() {
+            if !self.data.recipes.contains_key(node_id) {
+                continue;
+            }
+            let product: String = self.data.get_products(node_id).remove(0);
+            let power: f64 = self.data.get_recipe_power(node_id) / self.data.get_product_rate(node_id, &product) * self.get_overclock_power_factor(node_id);
+            terms.extend(links.iter().filter(|&l| l.item == product).map(|l| (l.variable, power)));
+        }
+        return terms;
+    }
+
+    //Clocks are only planned after solving, so the model assumes every machine may run at the fastest clock the plan can use
+    fn get_max_planned_clock(&self) -> f64 {
+        if !self.clock_policy.uses_shards() {
+            return 1.0;
+        }
+        return self.clock_policy.get_clock_levels().last().cloned().unwrap_or(1.0);
+    }
+
+    fn get_power_exponent(&self, recipe_id: &str) -> f64 {
+        return match self.data.get_recipe(recipe_id).produced_in.first() {
+            Some(building_id) => self.data.get_building(building_id).metadata.power_consumption_exponent,
+            None => 1.0,
+        };
+    }
+
+    //Power per item grows with clock^(exponent - 1), so pricing it at the fastest planned clock
+    //keeps a self-powered factory supplied whatever clocks are chosen
+    fn get_overclock_power_factor(&self, recipe_id: &str) -> f64 {
+        return self.get_max_planned_clock().powf(self.get_power_exponent(recipe_id) - 1.0);
+    }
+
+    fn get_power_production_terms(&self) -> Vec<(Variable, f64)> {
+        let mut terms: Vec<(Variable, f64)> = Vec::new();
+        for (node_id, generator_node) in self.generators.iter() {
+            let power: f64 = self.data.get_item(&generator_node.fuel).energy_value / 60.0;
+            terms.extend(self.links.get_incoming_for_item(node_id, &generator_node.fuel).iter().map(|&l| (l.variable, power)));
+        }
+        return terms;
+    }
+
+    //Machines run at 100% unless shards are spent on them, so with a shard budget each node also counts its shards,
+    //each adding 50% of a machine, and the budget row caps them across the whole factory
+    fn add_building_variables(&mut self) -> () {
+        let max_shards: u32 = if self.clock_policy.uses_shards() { self.clock_policy.get_max_shards() } else { 0 };
+        let mut shard_terms: Vec<(Variable, f64)> = Vec::new();
+        let recipe_ids: Vec<String> = self.links.links.keys().filter(|&node_id| self.data.recipes.contains_key(node_id)).cloned().collect::<Vec<String>>();
+        for recipe_id in recipe_ids {
+            let product: String = self.data.get_products(&recipe_id).remove(0);
+            let rate: f64 = self.data.get_product_rate(&recipe_id, &product);
+            let variable: Variable = self.model.add_var(&format!("Buildings_{}", recipe_id), self.building_coefficient, (0.0, f64::INFINITY));
+            let mut terms: Vec<(Variable, f64)> = Vec::new();
+            for link in self.links.get_outgoing_for_item(&recipe_id, &product) {
+                terms.push((link.variable, 1.0));
+            }
+            terms.push((variable, -rate));
+            if max_shards > 0 {
+                let shards: Variable = self.model.add_var(&format!("Shards_{}", recipe_id), 0.0, (0.0, f64::INFINITY));
+                terms.push((shards, -rate * 0.5));
+                self.model.add_constraint(&format!("Shards_{}", recipe_id), vec![(shards, 1.0), (variable, -(max_shards as f64))], ComparisonOp::Le, 0.0);
+                shard_terms.push((shards, 1.0));
+                self.shard_variables.insert(recipe_id.clone(), shards);
+            }
+            self.model.add_constraint(&format!("Buildings_{}", recipe_id), terms, ComparisonOp::Le, 0.0);
+            self.building_variables.insert(recipe_id, variable);
+        }
+        if !shard_terms.is_empty() {
+            self.model.add_constraint("Shards", shard_terms, ComparisonOp::Le, self.clock_policy.shard_budget as f64);
+        }
+    }
+
+    fn get_preference_weight(&self, recipe_id: &str) -> f64 {
+        let mut weight: f64 = self.recipe_weights.get(recipe_id).cloned().unwrap_or(0.0);
+        for building_id in self.data.get_recipe(recipe_id).produced_in.iter() {
+            weight += self.building_weights.get(building_id).cloned().unwrap_or(0.0);
+        }
+        return weight;
+    }
+
+    fn add_preference_variables(&mut self) -> () {
+        for recipe_id in self.get_recipe_nodes() {
+            let weight: f64 = self.get_preference_weight(&recipe_id);
+            if weight == 0.0 {
+                continue;
+            }
+            let product: String = self.data.get_products(&recipe_id).remove(0);
+            let rate: f64 = self.data.get_product_rate(&recipe_id, &product);
+            let variable: Variable = self.model.add_var(&format!("Preference_{}", recipe_id), weight, (0.0, f64::INFINITY));
+            let mut terms: Vec<(Variable, f64)> = Vec::new();
+            for link in self.links.get_outgoing_for_item(&recipe_id, &product) {
+                terms.push((link.variable, 1.0));
+            }
+            terms.push((variable, -rate));
+            self.model.add_constraint(&format!("Preference_{}", recipe_id), terms, ComparisonOp::Eq, 0.0);
+        }
+    }
+
+    fn get_machine_load(&self, recipe_id: &str, solution: &LpSolution) -> f64 {
+        let product: String = self.data.get_products(recipe_id).remove(0);
+        let rate: f64 = self.links.get_outgoing_for_item(recipe_id, &product).iter().fold(0.0, |acc, &l| acc + solution[l.variable]);
+        return rate / self.data.get_product_rate(recipe_id, &product);
+    }
+
+    fn get_building_count(&self, recipe_id: &str, solution: &LpSolution) -> f64 {
+        if let Some(&variable) = self.building_variables.get(recipe_id) {
+            return solution[variable].round();
+        }
+        return self.get_machine_load(recipe_id, solution);
+    }
+
+    //LowerPower never spends shards. Integer FewerMachines plans follow the model's building counts, which the shard
+    //budget already limits; otherwise the budget is spent greedily
+    fn plan_clocks(&self, solution: &LpSolution) -> HashMap<String, ClockSetting> {
+        let mut loads: HashMap<String, f64> = HashMap::new();
+        for recipe_id in self.links.links.keys().filter(|&node_id| self.data.recipes.contains_key(node_id)) {
+            let load: f64 = self.get_machine_load(recipe_id, solution);
+            if load > CLOCK_TOLERANCE {
+                loads.insert(recipe_id.clone(), load);
+            }
+        }
+        if self.clock_policy.preference == ClockPreference::LowerPower {
+            return loads.iter().map(|(recipe_id, &load)| {
+                let min_buildings: u32 = if self.building_variables.contains_key(recipe_id) { self.get_building_count(recipe_id, solution) as u32 } else { 0 };
+                return (recipe_id.clone(), self.get_underclock_setting(recipe_id, load, min_buildings));
+            }).collect::<HashMap<String, ClockSetting>>();
+        }
+        if !self.building_variables.is_empty() {
+            let max_shards: u32 = self.clock_policy.get_max_shards();
+            return loads.iter().map(|(recipe_id, &load)| {
+                return (recipe_id.clone(), get_shard_setting(load, self.get_building_count(recipe_id, solution) as u32, max_shards));
+            }).collect::<HashMap<String, ClockSetting>>();
+        }
+        let levels: Vec<f64> = self.clock_policy.get_clock_levels();
+        let mut plan: HashMap<String, (usize, ClockSetting)> = loads.iter().map(|(recipe_id, &load)| (recipe_id.clone(), (0, get_level_setting(load, 1.0)))).collect::<HashMap<String, (usize, ClockSetting)>>();
+        //Spend shards where they remove the most machines per shard
+        let mut remaining_shards: u32 = self.clock_policy.shard_budget;
+        loop {
+            let mut best: Option<(String, usize, ClockSetting, u32, f64)> = None;
+            for (recipe_id, (level, setting)) in plan.iter() {
+                let buildings: u32 = setting.get_buildings();
+                for (next_level, &next_clock) in levels.iter().enumerate().skip(level + 1) {
+                    let next_setting: ClockSetting = get_level_setting(loads[recipe_id], next_clock);
+                    let next_buildings: u32 = next_setting.get_buildings();
+                    let extra_shards: u32 = next_setting.get_shards().saturating_sub(setting.get_shards());
+                    if next_buildings >= buildings || extra_shards > remaining_shards {
+                        continue;
+                    }
+                    let value: f64 = (buildings - next_buildings) as f64 / extra_shards.max(1) as f64;
+                    if best.as_ref().is_none_or(|(_, _, _, _, best_value)| value > *best_value) {
+                        best = Some((recipe_id.clone(), next_level, next_setting, extra_shards, value));
+                    }
+                }
+            }
+            match best {
+                Some((recipe_id, level, setting, extra_shards, _)) => {
+                    remaining_shards -= extra_shards;
+                    plan.insert(recipe_id, (level, setting));
+                }
+                None => break,
+            }
+        }
+        return plan.into_iter().map(|(recipe_id, (_, setting))| (recipe_id, setting)).collect::<HashMap<String, ClockSetting>>();
+    }
+
+    //Mirrors the planned setting, so the machines always add up to the node's buildings and shards
+    fn get_machine_count(&self, recipe_id: &str, setting: &ClockSetting) -> MachineCount {
+        let item_id: String = self.data.get_products(recipe_id).remove(0);
+        return MachineCount {
+            rate_per_machine: self.data.get_product_rate(recipe_id, &item_id),
+            item_id,
+            full_machines: setting.full_machines.clone(),
+            remainder_clock: setting.remainder_clock,
+        };
+    }
+
+    //The average clock over the node's machines, as Node::clock is
+    fn get_exact_clock(&self, recipe_id: &str, setting: &ClockSetting, solution: &LpSolution) -> Option<BigRational> {
+        if setting.get_buildings() == 0 {
+            return None;
+        }
+        let recipe: &Recipe = self.data.get_recipe(recipe_id);
+        let product: &ItemQuantity = &recipe.products[0];
+        let mut rate: BigRational = exact::to_rational(0.0);
+        for link in self.links.get_outgoing_for_item(recipe_id, &product.item) {
+            rate += solution.get_exact(link.variable)?;
+        }
+        let rate_per_machine: BigRational = exact::to_rational(product.amount) * exact::to_rational(60.0) / exact::to_rational(recipe.time);
+        return Some(rate / rate_per_machine / exact::to_rational(setting.get_buildings() as f64));
+    }
+
+    //Spreads the load evenly, adding machines while each saves at least machine_cost of one machine's power at 100%.
+    //With power growing as clock^exponent, n machines draw load^exponent * n^(1 - exponent) machines' worth
+    fn get_underclock_setting(&self, recipe_id: &str, load: f64, min_buildings: u32) -> ClockSetting {
+        let exponent: f64 = self.get_power_exponent(recipe_id);
+        let get_power = |buildings: u32| -> f64 { load.powf(exponent) * (buildings as f64).powf(1.0 - exponent) };
+        let mut buildings: u32 = ((load - CLOCK_TOLERANCE).ceil() as u32).max(min_buildings).max(1);
+        while load / (buildings + 1) as f64 >= self.clock_policy.min_clock - CLOCK_TOLERANCE && get_power(buildings) - get_power(buildings + 1) >= self.clock_policy.machine_cost {
+            buildings += 1;
+        }
+        return get_even_setting(load, buildings);
+    }
+
+    fn get_node_power(&self, recipe_id: &str, setting: &ClockSetting) -> f64 {
+        let exponent: f64 = self.get_power_exponent(recipe_id);
+        return setting.get_clocks().iter().fold(0.0, |acc, &(machines, clock)| acc + machines as f64 * self.data.get_recipe_power(recipe_id) * clock.powf(exponent));
+    }
+
+    fn build_model(&mut self) -> () {
+        self.excluded_recipes = self.disallowed_recipes.clone();
+        self.expanded_recipes.clear();
+        self.model = LinearProgram::new();
+        self.links = LinkSet::new();
+        self.generators.clear();
+        self.building_variables.clear();
+        self.shard_variables.clear();
         let mut infeasible_recipes: Vec<String> = Vec::new();
-        for recipe_id in self.data.recipes.keys().filter(|&recipe_id| !self.disallowed_recipes.contains(recipe_id)) {
+        for recipe_id in self.data.recipes.keys().filter(|&recipe_id| !self.excluded_recipes.contains(recipe_id)) {
             if !self.is_feasible(recipe_id) {
                 infeasible_recipes.push(recipe_id.clone());
             }
         }
         for recipe_id in infeasible_recipes {
-            self.remove_recipe(&recipe_id);
+            self.excluded_recipes.insert(recipe_id.clone());
             self.trim_parents(&recipe_id);
         }
         let mut recipes_to_add: Vec<String> = Vec::new();
-        for (output_item_id, output_item_rate) in self.targets.iter() {
+        for output_item_id in self.targets.keys() {
             if let None = self.data.items.get(output_item_id) {
                 panic!("\"{}\" is not an item", output_item_id);
             }
-            for output_recipe_id in self.data.get_item_creators(output_item_id, &self.disallowed_recipes) {
-                self.links.add_simple_variable(&output_recipe_id, output_item_id, output_item_id, &mut self.problem);
+            let weight: f64 = 1.0 - self.target_values.get(output_item_id).cloned().unwrap_or(0.0);
+            for output_recipe_id in self.data.get_item_creators(output_item_id, &self.excluded_recipes) {
+                self.links.add_weighted_variable(&output_recipe_id, output_item_id, output_item_id, weight, &mut self.model);
                 recipes_to_add.push(output_recipe_id);
             }
-            self.problem.add_constraint(self.links.get_incoming_for_item(output_item_id, output_item_id).iter().map(|&l| (l.variable, 1.0)), ComparisonOp::Eq, *output_item_rate);
         }
         for recipe_id in recipes_to_add {
             self.add_variables(&recipe_id);
         }
+        if self.self_powered {
+            self.add_generators();
+        }
         //This is kinda ass
-        for (node_id, links) in self.links.links.clone().iter() {
-            if node_id.starts_with("Desc_") {
-                continue;
+        let mut processed: HashSet<String> = HashSet::new();
+        loop {
+            let mut producer_ids: Vec<String> = self.get_recipe_nodes();
+            producer_ids.extend(self.generators.keys().cloned());
+            producer_ids.retain(|node_id| !processed.contains(node_id));
+            if producer_ids.is_empty() {
+                break;
             }
-            'outer: for product in self.data.get_products(node_id) {
-                for link in links {
-                    if link.item == product {
-                        continue 'outer;
+            for node_id in producer_ids.iter() {
+                processed.insert(node_id.clone());
+                for product in self.get_node_products(node_id) {
+                    if self.links.get_outgoing_for_item(node_id, &product).is_empty() {
+                        self.add_byproduct_variables(node_id, &product);
                     }
                 }
-                self.links.add_weighted_variable(node_id, &product, &product, self.byproduct_coefficient, &mut self.problem);
             }
         }
-        for (node_id, _) in self.links.links.iter() {
-            if node_id.starts_with("Desc_") {
-                continue;
+        for (node_id, generator_node) in self.generators.iter() {
+            let generator: &Generator = self.data.generators.get(&generator_node.generator).unwrap();
+            let energy: f64 = self.data.get_item(&generator_node.fuel).energy_value;
+            let fuel_inputs: Vec<&Link> = self.links.get_incoming_for_item(node_id, &generator_node.fuel);
+            if generator.water_to_power_ratio > 0.0 {
+                let mut terms: Vec<(Variable, f64)> = Vec::new();
+                for link in self.links.get_incoming_for_item(node_id, WATER_ID) {
+                    terms.push((link.variable, 1000.0));
+                }
+                for link in fuel_inputs.iter() {
+                    terms.push((link.variable, -energy * generator.water_to_power_ratio));
+                }
+                self.model.add_constraint(&format!("Water_{}", node_id), terms, ComparisonOp::Eq, 0.0);
             }
+            if let Some((waste, waste_per_fuel)) = get_fuel_waste(&generator_node.fuel) {
+                let mut terms: Vec<(Variable, f64)> = Vec::new();
+                for link in self.links.get_outgoing_for_item(node_id, waste) {
+                    terms.push((link.variable, 1.0));
+                }
+                for link in fuel_inputs.iter() {
+                    terms.push((link.variable, -waste_per_fuel));
+                }
+                self.model.add_constraint(&format!("Waste_{}", node_id), terms, ComparisonOp::Eq, 0.0);
+            }
+        }
+        if self.self_powered {
+            let mut terms: Vec<(Variable, f64)> = self.get_power_production_terms();
+            terms.extend(self.get_power_consumption_terms().into_iter().map(|(variable, power)| (variable, -power)));
+            self.model.add_constraint("Power", terms, ComparisonOp::Ge, 0.0);
+        }
+        for node_id in self.get_recipe_nodes().iter() {
             let recipe: &Recipe = self.data.recipes.get(node_id).unwrap();
-            for ItemQuantity { item: ingredient, amount: in_rate } in recipe.ingredients.iter() {
-                for ItemQuantity { item: product, amount: out_rate } in recipe.products.iter() {
-                    let inputs: Vec<&Link> = self.links.get_incoming_for_item(node_id, ingredient);
-                    let outputs: Vec<&Link> = self.links.get_outgoing_for_item(node_id, product);
-                    let initial_lhs: Vec<(&Link, f64)> = inputs.iter().map(|&l| (l, *out_rate)).chain(outputs.iter().map(|&l| (l, -*in_rate))).collect::<Vec<(&Link, f64)>>();
-                    let mut temp_lhs: HashMap<usize, Vec<(&Link, f64)>> = HashMap::new();
-                    for (link, coefficient) in initial_lhs {
-                        let idx: usize = link.variable.idx();
-                        if temp_lhs.contains_key(&idx) {
-                            temp_lhs.get_mut(&idx).unwrap().push((link, coefficient));
-                        } else {
-                            temp_lhs.insert(idx, vec![(link, coefficient)]);
-                        }
-                    }
-                    let mut lhs: LinearExpr = LinearExpr::empty();
-                    for var_group in temp_lhs.values() {
-                        let coefficient: f64 = var_group.iter().fold(0.0, |acc, &(_, coefficient)| acc + coefficient);
-                        lhs.add(var_group.get(0).unwrap().0.variable, coefficient);
-                    }
-                    self.problem.add_constraint(lhs, ComparisonOp::Eq, 0.0);
+            //Each item's links are looked up once per node rather than once per ingredient and product pair
+            let ingredient_links: Vec<Vec<&Link>> = recipe.ingredients.iter().map(|ingredient| self.links.get_incoming_for_item(node_id, &ingredient.item)).collect::<Vec<Vec<&Link>>>();
+            let product_links: Vec<Vec<&Link>> = recipe.products.iter().map(|product| self.links.get_outgoing_for_item(node_id, &product.item)).collect::<Vec<Vec<&Link>>>();
+            for (ItemQuantity { item: ingredient, amount: in_rate }, inputs) in recipe.ingredients.iter().zip(ingredient_links.iter()) {
+                for (ItemQuantity { item: product, amount: out_rate }, outputs) in recipe.products.iter().zip(product_links.iter()) {
+                    let terms: Vec<(Variable, f64)> = inputs.iter().map(|&l| (l.variable, *out_rate)).chain(outputs.iter().map(|&l| (l.variable, -*in_rate))).collect::<Vec<(Variable, f64)>>();
+                    self.model.add_constraint(&format!("Balance_{}__{}__{}", node_id, ingredient, product), terms, ComparisonOp::Eq, 0.0);
                 }
             }
         }
-        let solution: Solution = self.problem.solve().unwrap();
+        self.add_preference_variables();
+        if self.integer_buildings {
+            self.add_building_variables();
+        }
+        self.model_built = true;
+    }
+
+    //Rates and limits are kept out of the built model so changing them doesn't require a rebuild
+    fn add_bound_constraints(&self, model: &mut LinearProgram) -> () {
+        for (output_item_id, target) in self.targets.iter() {
+            let name: String = get_target_constraint_name(output_item_id);
+            let output_links: Vec<(Variable, f64)> = self.links.get_incoming_for_item(output_item_id, output_item_id).iter().map(|&l| (l.variable, 1.0)).collect::<Vec<(Variable, f64)>>();
+            match *target {
+                Target::Exact(rate) => model.add_constraint(&name, output_links, ComparisonOp::Eq, rate),
+                Target::AtLeast(rate) => model.add_constraint(&name, output_links, ComparisonOp::Ge, rate),
+                Target::AtMost(rate) => model.add_constraint(&name, output_links, ComparisonOp::Le, rate),
+                Target::Range(min_rate, max_rate) => {
+                    model.add_constraint(&format!("{}_min", name), output_links.clone(), ComparisonOp::Ge, min_rate);
+                    model.add_constraint(&format!("{}_max", name), output_links, ComparisonOp::Le, max_rate);
+                }
+            }
+        }
+        for ratio in self.ratios.iter() {
+            for pair in ratio.windows(2) {
+                let (first_item_id, first_amount) = &pair[0];
+                let (second_item_id, second_amount) = &pair[1];
+                let mut terms: Vec<(Variable, f64)> = Vec::new();
+                terms.extend(self.links.get_incoming_for_item(first_item_id, first_item_id).iter().map(|&l| (l.variable, 1.0 / first_amount)));
+                terms.extend(self.links.get_incoming_for_item(second_item_id, second_item_id).iter().map(|&l| (l.variable, -1.0 / second_amount)));
+                model.add_constraint(&format!("Ratio_{}__{}", first_item_id, second_item_id), terms, ComparisonOp::Eq, 0.0);
+            }
+        }
+        for (item_id, links) in self.links.links.iter().filter(|&(node_id, _)| self.resources.contains(node_id)) {
+            let limit: f64 = self.resources.get_limit(item_id);
+            if limit.is_finite() {
+                model.add_constraint(&get_limit_constraint_name(item_id), links.iter().map(|l| (l.variable, 1.0)).collect::<Vec<(Variable, f64)>>(), ComparisonOp::Le, limit);
+            }
+        }
+        for (item_id, import) in self.imports.iter() {
+            let import_links: Vec<&Link> = self.links.links.get(&get_import_node_id(item_id)).map_or(Vec::new(), |links| links.iter().collect::<Vec<&Link>>());
+            let op: ComparisonOp = if import.required { ComparisonOp::Eq } else { ComparisonOp::Le };
+            model.add_constraint(&format!("Import_{}", item_id), import_links.iter().map(|&l| (l.variable, 1.0)).collect::<Vec<(Variable, f64)>>(), op, import.limit);
+        }
+    }
+
+    pub fn solve(&mut self) -> Factory {
+        return self.try_solve().unwrap();
+    }
+
+    //The model exactly as the backend would receive it
+    fn get_full_model(&mut self) -> (LinearProgram, Vec<Variable>) {
+        if !self.model_built {
+            self.build_model();
+        }
+        let mut model: LinearProgram = self.model.clone();
+        self.add_bound_constraints(&mut model);
+        let mut integer_variables: Vec<Variable> = self.building_variables.values().chain(self.shard_variables.values()).cloned().collect::<Vec<Variable>>();
+        integer_variables.sort_by_key(|variable| variable.idx());
+        return (model, integer_variables);
+    }
+
+    pub fn get_model_stats(&mut self) -> ModelStats {
+        let (model, integer_variables): (LinearProgram, Vec<Variable>) = self.get_full_model();
+        return model.stats(&integer_variables);
+    }
+
+    #[allow(dead_code)]
+    pub fn export_model(&mut self, format: ModelFormat) -> String {
+        let (model, integer_variables): (LinearProgram, Vec<Variable>) = self.get_full_model();
+        return export::export(&model, &integer_variables, format);
+    }
+
+    #[allow(dead_code)]
+    pub fn write_model(&mut self, path: &str, format: ModelFormat) -> () {
+        fs::write(path, self.export_model(format)).expect("Couldn't write file");
+    }
+
+    pub fn try_solve(&mut self) -> Result<Factory, SolveError> {
+        let (model, integer_variables): (LinearProgram, Vec<Variable>) = self.get_full_model();
+        let solution: LpSolution = self.backend.solve(&model, &integer_variables)?;
         let mut factory: Factory = Factory::new();
-        let mut node_type: NodeType;
+        factory.objective = solution.objective;
+        factory.optimal = solution.optimal;
+        for (index, constraint) in model.constraints().iter().enumerate() {
+            if let Some(dual) = solution.get_dual(index) {
+                factory.duals.insert(constraint.name.clone(), dual);
+            }
+        }
+        factory.targets = self.targets.clone();
+        for item_id in self.resources.item_ids() {
+            factory.resource_limits.insert(item_id.clone(), self.resources.get_limit(&item_id));
+        }
+        let clock_settings: HashMap<String, ClockSetting> = self.plan_clocks(&solution);
+        factory.power_consumption = self.get_extraction_power_terms().iter().fold(0.0, |acc, &(variable, power)| acc + solution[variable] * power);
+        factory.power_production = self.get_power_production_terms().iter().fold(0.0, |acc, &(variable, power)| acc + solution[variable] * power);
+        for links in self.links.links.values() {
+            for link in links.iter().filter(|&l| l.destination.starts_with("Sink_")) {
+                factory.sink_points += solution[link.variable] * self.data.get_item(&link.item).sink_points;
+            }
+            for link in links.iter().filter(|&l| l.destination.starts_with("Store_")) {
+                factory.stored_radioactivity += solution[link.variable] * self.data.get_item(&link.item).radioactive_decay;
+            }
+        }
         for (node_id, _) in self.links.links.iter() {
-            if node_id.starts_with("Desc_") {
-                if self.targets.contains_key(node_id) {
-                    node_type = NodeType::Output;
-                } else {
-                    node_type = NodeType::Input;
+            factory.add_node(node_id, &self.get_node_name(node_id), self.get_node_type(node_id));
+            if self.data.recipes.contains_key(node_id) {
+                if let Some(setting) = clock_settings.get(node_id) {
+                    let node: &mut Node = factory.nodes.get_mut(node_id).unwrap();
+                    node.buildings = setting.get_buildings() as f64;
+                    node.clock = setting.get_average_clock();
+                    node.shards = setting.get_shards();
+                    node.power = self.get_node_power(node_id, setting);
+                    node.machines = Some(self.get_machine_count(node_id, setting));
+                    node.exact_clock = self.get_exact_clock(node_id, setting, &solution);
+                    factory.power_consumption += node.power;
                 }
-                factory.add_node(node_id, &self.data.get_item_name(node_id), node_type);
-            } else {
-                factory.add_node(node_id, &self.data.get_recipe_name(node_id), NodeType::Production);
+            }
+        }
+        for node_id in self.generators.keys() {
+            if !factory.nodes.contains_key(node_id) {
+                factory.add_node(node_id, &self.get_node_name(node_id), NodeType::Generator);
             }
         }
         for (node_id, links) in self.links.links.iter() {
             for link in links.iter() {
                 let item_name: &String = &self.data.get_item_name(&link.item);
-                let item_rate: f64 = solution[link.variable];
-                let other_node_name: String = if link.destination.starts_with("Desc_") { self.data.get_item_name(&link.destination) } else { self.data.get_recipe_name(&link.destination) };
-                let this_node_name: String = if node_id.starts_with("Desc_") { self.data.get_item_name(node_id) } else { self.data.get_recipe_name(node_id) };
-                factory.add_node_output(node_id, &link.destination, ItemRate { name: item_name.clone(), other_node_name, rate: item_rate, underclock: self.get_underclock(node_id, &link.item, item_rate) });
+                let item_rate: f64 = if self.snap_rates && solution.exact_values.is_none() { numeric::snap(solution[link.variable]) } else { solution[link.variable] };
+                let exact_rate: Option<BigRational> = solution.get_exact(link.variable);
+                let other_node_name: String = self.get_node_name(&link.destination);
+                let this_node_name: String = self.get_node_name(node_id);
+                factory.add_node_output(node_id, &link.destination, &link.item, ItemRate { item_id: link.item.clone(), name: item_name.clone(), other_node_id: link.destination.clone(), other_node_name, rate: item_rate, exact_rate: exact_rate.clone() });
                 if !factory.nodes.contains_key(&link.destination) {
-                    factory.add_node(&link.destination, &self.data.get_item_name(&link.destination), NodeType::Output);
+                    factory.add_node(&link.destination, &self.get_node_name(&link.destination), self.get_node_type(&link.destination));
                 }
-                factory.add_node_input(&link.destination, node_id, ItemRate { name: item_name.clone(), other_node_name: this_node_name, rate: item_rate, underclock: 0.0 })
+                factory.add_node_input(&link.destination, node_id, &link.item, ItemRate { item_id: link.item.clone(), name: item_name.clone(), other_node_id: node_id.clone(), other_node_name: this_node_name, rate: item_rate, exact_rate })
             }
         }
         for (node_id, node) in factory.nodes.clone().iter() {
             let production_node: &mut Node = factory.nodes.get_mut(node_id).unwrap();
-            for (item_id, item_rate) in node.inputs.iter() {
-                if item_rate.rate.abs() <= 0.00001 {
-                    production_node.inputs.remove(item_id);
+            for (edge, item_rate) in node.inputs.iter() {
+                if item_rate.rate.abs() <= self.prune_epsilon {
+                    production_node.inputs.remove(edge);
                 }
             }
-            for (item_id, item_rate) in node.outputs.iter() {
-                if item_rate.rate.abs() <= 0.00001 {
-                    production_node.outputs.remove(item_id);
+            for (edge, item_rate) in node.outputs.iter() {
+                if item_rate.rate.abs() <= self.prune_epsilon {
+                    production_node.outputs.remove(edge);
                 }
             }
             if production_node.inputs.is_empty() && production_node.outputs.is_empty() {
                 factory.nodes.remove(node_id);
             }
         }
-        return factory;
+        for node in factory.nodes.values_mut() {
+            self.set_node_building(node);
+        }
+        factory.mark_cycles();
+        return Ok(factory);
+    }
+}
+
+fn get_fuel_waste(fuel: &str) -> Option<(&'static str, f64)> {
+    return match fuel {
+        "Desc_NuclearFuelRod_C" => Some(("Desc_NuclearWaste_C", 50.0)),
+        "Desc_PlutoniumFuelRod_C" => Some(("Desc_PlutoniumWaste_C", 10.0)),
+        _ => None,
+    };
+}
+
+fn get_item_total(item_rates: &HashMap<(String, String), ItemRate>, item_id: &str) -> f64 {
+    return item_rates.values().filter(|&item_rate| item_rate.item_id == item_id).fold(0.0, |acc, item_rate| acc + item_rate.rate);
+}
+
+//Every item of a recipe node should imply the same number of machines at 100% clock;
+//the magnitude is how far an item's flow is from what the first product's flow implies
+fn verify_recipe_node(data: &GameData, recipe_id: &str, node: &Node) -> Vec<Violation> {
+    let mut violations: Vec<Violation> = Vec::new();
+    let recipe: &Recipe = data.get_recipe(recipe_id);
+    let reference_item: &str = &recipe.products[0].item;
+    let load: f64 = get_item_total(&node.outputs, reference_item) / data.get_product_rate(recipe_id, reference_item);
+    let mut flows: Vec<(&String, f64, f64)> = Vec::new();
+    for ItemQuantity { item, .. } in recipe.ingredients.iter() {
+        flows.push((item, get_item_total(&node.inputs, item), data.get_ingredient_rate(recipe_id, item)));
+    }
+    for ItemQuantity { item, .. } in recipe.products.iter() {
+        flows.push((item, get_item_total(&node.outputs, item), data.get_product_rate(recipe_id, item)));
+    }
+    for (item_id, rate, per_machine) in flows {
+        let magnitude: f64 = rate - load * per_machine;
+        if magnitude.abs() > VERIFY_TOLERANCE * per_machine.max(1.0) {
+            violations.push(Violation { kind: ViolationKind::RecipeRatio, node_id: recipe_id.to_string(), item_id: item_id.clone(), magnitude });
+        }
+    }
+    let is_recipe_item = |item_id: &str, items: &Vec<ItemQuantity>| items.iter().any(|item_quantity| item_quantity.item == item_id);
+    for item_rate in node.inputs.values().filter(|&item_rate| !is_recipe_item(&item_rate.item_id, &recipe.ingredients)) {
+        violations.push(Violation { kind: ViolationKind::UnknownItem, node_id: recipe_id.to_string(), item_id: item_rate.item_id.clone(), magnitude: item_rate.rate });
+    }
+    for item_rate in node.outputs.values().filter(|&item_rate| !is_recipe_item(&item_rate.item_id, &recipe.products)) {
+        violations.push(Violation { kind: ViolationKind::UnknownItem, node_id: recipe_id.to_string(), item_id: item_rate.item_id.clone(), magnitude: item_rate.rate });
+    }
+    return violations;
+}
+
+fn get_generator_count(node: &Node) -> f64 {
+    let mut count: f64 = 0.0;
+    for item_rate in node.inputs.values() {
+        if let Some(&rate) = node.building_inputs.get(&item_rate.item_id) {
+            count = f64::max(count, item_rate.rate / rate);
+        }
+    }
+    return count;
+}
+
+fn get_import_node_id(item_id: &str) -> String {
+    return format!("Import_{}", item_id);
+}
+
+//Range targets add "_min" and "_max" to this for their two bounds
+pub fn get_target_constraint_name(item_id: &str) -> String {
+    return format!("Target_{}", item_id);
+}
+
+pub fn get_limit_constraint_name(item_id: &str) -> String {
+    return format!("Limit_{}", item_id);
+}
+
+//Each shard adds 50% to one machine's clock
+fn get_clock_shards(clock: f64) -> u32 {
+    return ((clock - 1.0 - CLOCK_TOLERANCE) / 0.5).ceil().max(0.0) as u32;
+}
+
+fn get_even_setting(load: f64, buildings: u32) -> ClockSetting {
+    return ClockSetting { full_machines: vec![(buildings, load / buildings as f64)], remainder_clock: None };
+}
+
+//As many machines as the load fills at this clock and one remainder machine for the rest
+fn get_level_setting(load: f64, clock: f64) -> ClockSetting {
+    let full: u32 = (load / clock + CLOCK_TOLERANCE).floor() as u32;
+    let remainder: f64 = load - full as f64 * clock;
+    let remainder_clock: Option<f64> = if remainder > CLOCK_TOLERANCE { Some(remainder) } else { None };
+    let full_machines: Vec<(u32, f64)> = if full > 0 { vec![(full, clock)] } else { Vec::new() };
+    return ClockSetting { full_machines, remainder_clock };
+}
+
+//The fewest shards that let this many machines carry the load: as many machines as possible at the top clock,
+//one with the shards left over and the rest at 100%. The spare capacity is under one shard's worth, so taking it
+//off the partly sharded machine, or else the slowest, makes the remainder machine without freeing a shard
+fn get_shard_setting(load: f64, buildings: u32, max_shards: u32) -> ClockSetting {
+    if buildings == 0 {
+        return ClockSetting { full_machines: Vec::new(), remainder_clock: None };
     }
+    let shards: u32 = (2.0 * (load - buildings as f64) - CLOCK_TOLERANCE).ceil().max(0.0) as u32;
+    if shards == 0 && load <= (buildings - 1) as f64 + CLOCK_TOLERANCE {
+        return get_even_setting(load, buildings);
+    }
+    let top: u32 = shards.checked_div(max_shards).unwrap_or(0);
+    let partial_shards: u32 = shards.checked_rem(max_shards).unwrap_or(0);
+    let partial: u32 = (partial_shards > 0) as u32;
+    let mut full_machines: Vec<(u32, f64)> = vec![
+        (top, 1.0 + 0.5 * max_shards as f64),
+        (partial, 1.0 + 0.5 * partial_shards as f64),
+        (buildings.saturating_sub(top + partial), 1.0),
+    ].into_iter().filter(|&(machines, _)| machines > 0).collect::<Vec<(u32, f64)>>();
+    let spare: f64 = full_machines.iter().fold(0.0, |acc, &(machines, clock)| acc + machines as f64 * clock) - load;
+    if spare <= CLOCK_TOLERANCE {
+        return ClockSetting { full_machines, remainder_clock: None };
+    }
+    let index: usize = if partial > 0 { (top > 0) as usize } else { full_machines.len() - 1 };
+    full_machines[index].0 -= 1;
+    let remainder_clock: Option<f64> = Some(full_machines[index].1 - spare);
+    full_machines.retain(|&(machines, _)| machines > 0);
+    return ClockSetting { full_machines, remainder_clock };
+}
+
+fn get_link_name(source: &str, item: &str, destination: &str) -> String {
+    return format!("{}__{}__{}", source, item, destination);
 }
 
 #[derive(Clone)]
 pub struct ItemRate {
+    pub item_id: String,
     pub name: String,
+    #[allow(dead_code)]
+    pub other_node_id: String,
     pub other_node_name: String,
     pub rate: f64,
-    pub underclock: f64,
+    pub exact_rate: Option<BigRational>,
 }
 
-#[derive(Clone)]
+impl ItemRate {
+    //Parallel links between the same two nodes are reported as one edge
+    fn merge(&mut self, other: &ItemRate) -> () {
+        self.rate += other.rate;
+        self.exact_rate = match (self.exact_rate.take(), &other.exact_rate) {
+            (Some(exact_rate), Some(other_exact_rate)) => Some(exact_rate + other_exact_rate),
+            _ => None,
+        };
+    }
+}
+
+//Resources with a known extraction building are Extractors, any other resource is a RawResource;
+//Byproduct is surplus sent out of the factory, Storage surplus kept in storage and Sink surplus fed to the AWESOME Sink
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum NodeType {
-    Input,
+    RawResource,
+    Extractor,
+    Import,
     Production,
-    Output,
+    Generator,
+    Target,
+    Byproduct,
+    Storage,
+    Sink,
+}
+
+//Rates are for the node's first product at 100% clock. Full machines are (count, clock) pairs, fastest first,
+//and the remainder machine, if any, runs at whatever clock the rest of the load needs
+#[derive(Debug, Clone)]
+pub struct MachineCount {
+    pub item_id: String,
+    pub rate_per_machine: f64,
+    pub full_machines: Vec<(u32, f64)>,
+    pub remainder_clock: Option<f64>,
 }
 
 #[derive(Clone)]
 pub struct Node {
     pub node_type: NodeType,
+    pub id: String,
     pub name: String,
-    pub inputs: HashMap<String, ItemRate>,
-    pub outputs: HashMap<String, ItemRate>,
+    pub recipe_id: Option<String>,
+    pub building_id: Option<String>,
+    pub building_name: Option<String>,
+    pub building_inputs: HashMap<String, f64>,
+    pub building_outputs: HashMap<String, f64>,
+    pub buildings: f64,
+    //The average over the node's machines; machines has the clock each one runs at
+    pub clock: f64,
+    pub shards: u32,
+    pub power: f64,
+    pub machines: Option<MachineCount>,
+    pub exact_clock: Option<BigRational>,
+    pub in_cycle: bool,
+    //Edges are keyed by (peer node id, item id) so several items between the same pair of nodes stay separate
+    pub inputs: HashMap<(String, String), ItemRate>,
+    pub outputs: HashMap<(String, String), ItemRate>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FactorySummary {
+    pub machines: HashMap<String, f64>,
+    pub power_consumption: f64,
+    pub power_production: f64,
+    pub resources: HashMap<String, f64>,
+    pub imports: HashMap<String, f64>,
+    pub byproducts: HashMap<String, f64>,
+    pub stored: HashMap<String, f64>,
+    pub sunk: HashMap<String, f64>,
+    pub targets: HashMap<String, f64>,
+    pub sink_points: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ViolationKind {
+    RecipeRatio,
+    UnknownItem,
+    TargetMissed,
+    ResourceLimit,
+    NegativeFlow,
+}
+
+//Magnitudes are in items per minute
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub node_id: String,
+    pub item_id: String,
+    pub magnitude: f64,
 }
 
 pub struct Factory {
     pub nodes: HashMap<String, Node>,
+    pub objective: f64,
+    //False when the integer search hit its node limit, so a better plan may exist
+    pub optimal: bool,
+    pub power_consumption: f64,
+    pub power_production: f64,
+    pub sink_points: f64,
+    pub stored_radioactivity: f64,
+    pub cycles: Vec<Vec<String>>,
+    pub targets: HashMap<String, Target>,
+    pub resource_limits: HashMap<String, f64>,
+    //Change in objective per unit of each constraint's right hand side, by constraint name,
+    //when the backend provides duals
+    pub duals: HashMap<String, f64>,
 }
 
 impl Factory {
     fn new() -> Factory {
         return Factory {
             nodes: HashMap::new(),
+            objective: 0.0,
+            optimal: true,
+            power_consumption: 0.0,
+            power_production: 0.0,
+            sink_points: 0.0,
+            stored_radioactivity: 0.0,
+            cycles: Vec::new(),
+            targets: HashMap::new(),
+            resource_limits: HashMap::new(),
+            duals: HashMap::new(),
         };
     }
 
     fn add_node(&mut self, node_id: &str, name: &str, node_type: NodeType) -> () {
-        self.nodes.insert(node_id.to_string(), Node { node_type, name: name.to_string(), inputs: HashMap::new(), outputs: HashMap::new() });
+        self.nodes.insert(node_id.to_string(), Node { node_type, id: node_id.to_string(), name: name.to_string(), recipe_id: None, building_id: None, building_name: None, building_inputs: HashMap::new(), building_outputs: HashMap::new(), buildings: 0.0, clock: 0.0, shards: 0, power: 0.0, machines: None, exact_clock: None, in_cycle: false, inputs: HashMap::new(), outputs: HashMap::new() });
     }
 
-    fn add_node_input(&mut self, node_id: &str, source: &str, input: ItemRate) {
-        self.nodes.get_mut(node_id).unwrap().inputs.insert(source.to_string(), input);
+    fn add_node_input(&mut self, node_id: &str, source: &str, item_id: &str, input: ItemRate) {
+        let edge: (String, String) = (source.to_string(), item_id.to_string());
+        self.nodes.get_mut(node_id).unwrap().inputs.entry(edge).and_modify(|item_rate| item_rate.merge(&input)).or_insert(input);
     }
 
-    fn add_node_output(&mut self, node_id: &str, destination: &str, output: ItemRate) {
-        self.nodes.get_mut(node_id).unwrap().outputs.insert(destination.to_string(), output);
+    fn add_node_output(&mut self, node_id: &str, destination: &str, item_id: &str, output: ItemRate) {
+        let edge: (String, String) = (destination.to_string(), item_id.to_string());
+        self.nodes.get_mut(node_id).unwrap().outputs.entry(edge).and_modify(|item_rate| item_rate.merge(&output)).or_insert(output);
     }
-}
\ No newline at end of file
+
+    //Machines are keyed by building id and item totals by item id; generators are counted at full output
+    pub fn summary(&self) -> FactorySummary {
+        let mut summary: FactorySummary = FactorySummary {
+            machines: HashMap::new(),
+            power_consumption: self.power_consumption,
+            power_production: self.power_production,
+            resources: HashMap::new(),
+            imports: HashMap::new(),
+            byproducts: HashMap::new(),
+            stored: HashMap::new(),
+            sunk: HashMap::new(),
+            targets: HashMap::new(),
+            sink_points: self.sink_points,
+        };
+        for node in self.nodes.values() {
+            let (totals, item_rates) = match node.node_type {
+                NodeType::RawResource | NodeType::Extractor => (&mut summary.resources, &node.outputs),
+                NodeType::Import => (&mut summary.imports, &node.outputs),
+                NodeType::Byproduct => (&mut summary.byproducts, &node.inputs),
+                NodeType::Storage => (&mut summary.stored, &node.inputs),
+                NodeType::Sink => (&mut summary.sunk, &node.inputs),
+                NodeType::Target => (&mut summary.targets, &node.inputs),
+                NodeType::Production | NodeType::Generator => {
+                    if let Some(building_id) = &node.building_id {
+                        let machines: f64 = if node.node_type == NodeType::Generator { get_generator_count(node) } else { node.buildings };
+                        *summary.machines.entry(building_id.clone()).or_insert(0.0) += machines;
+                    }
+                    continue;
+                }
+            };
+            for item_rate in item_rates.values() {
+                *totals.entry(item_rate.item_id.clone()).or_insert(0.0) += item_rate.rate;
+            }
+        }
+        return summary;
+    }
+
+    pub fn verify(&self, data: &GameData) -> Vec<Violation> {
+        let mut violations: Vec<Violation> = Vec::new();
+        let add_violation = |violations: &mut Vec<Violation>, kind: ViolationKind, node_id: &str, item_id: &str, magnitude: f64| {
+            if magnitude.abs() > VERIFY_TOLERANCE {
+                violations.push(Violation { kind, node_id: node_id.to_string(), item_id: item_id.to_string(), magnitude });
+            }
+        };
+        for (node_id, node) in self.nodes.iter() {
+            for item_rate in node.inputs.values().chain(node.outputs.values()).filter(|&item_rate| item_rate.rate < 0.0) {
+                add_violation(&mut violations, ViolationKind::NegativeFlow, node_id, &item_rate.item_id, item_rate.rate);
+            }
+            if let Some(recipe_id) = &node.recipe_id {
+                violations.extend(verify_recipe_node(data, recipe_id, node));
+            }
+        }
+        for (item_id, target) in self.targets.iter() {
+            let rate: f64 = self.nodes.get(item_id).map_or(0.0, |node| get_item_total(&node.inputs, item_id));
+            let (min_rate, max_rate) = match *target {
+                Target::Exact(rate) => (rate, rate),
+                Target::AtLeast(rate) => (rate, f64::INFINITY),
+                Target::AtMost(rate) => (0.0, rate),
+                Target::Range(min_rate, max_rate) => (min_rate, max_rate),
+            };
+            let shortfall: f64 = if rate < min_rate { rate - min_rate } else if rate > max_rate { rate - max_rate } else { 0.0 };
+            add_violation(&mut violations, ViolationKind::TargetMissed, item_id, item_id, shortfall);
+        }
+        for (item_id, &limit) in self.resource_limits.iter() {
+            let rate: f64 = self.nodes.get(item_id)
+                .filter(|&node| matches!(node.node_type, NodeType::RawResource | NodeType::Extractor))
+                .map_or(0.0, |node| get_item_total(&node.outputs, item_id));
+            add_violation(&mut violations, ViolationKind::ResourceLimit, item_id, item_id, (rate - limit).max(0.0));
+        }
+        return violations;
+    }
+
+    //Only links that carry flow after pruning count, so unused loops in the model aren't reported
+    fn mark_cycles(&mut self) -> () {
+        let edges: HashMap<String, Vec<String>> = self.nodes.iter().map(|(node_id, node)| (node_id.clone(), node.outputs.keys().map(|(destination, _)| destination.clone()).collect::<Vec<String>>())).collect::<HashMap<String, Vec<String>>>();
+        self.cycles = graph::get_cycles(&edges);
+        for node_id in self.cycles.iter().flatten() {
+            if let Some(node) = self.nodes.get_mut(node_id) {
+                node.in_cycle = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_item_rate(rate: f64, exact_rate: Option<BigRational>) -> ItemRate {
+        return ItemRate { item_id: "Desc_Screw_C".to_string(), name: "Screw".to_string(), other_node_id: "Recipe_Screw_C".to_string(), other_node_name: "Screw".to_string(), rate, exact_rate };
+    }
+
+    #[test]
+    fn merges_parallel_links() {
+        let mut factory: Factory = Factory::new();
+        factory.add_node("Recipe_Rotor_C", "Rotor", NodeType::Production);
+        factory.add_node_input("Recipe_Rotor_C", "Recipe_Screw_C", "Desc_Screw_C", get_item_rate(1.0 / 3.0, Some(exact::to_rational(1.0 / 3.0))));
+        factory.add_node_input("Recipe_Rotor_C", "Recipe_Screw_C", "Desc_Screw_C", get_item_rate(2.0 / 3.0, Some(exact::to_rational(2.0 / 3.0))));
+        let input: &ItemRate = &factory.nodes["Recipe_Rotor_C"].inputs[&("Recipe_Screw_C".to_string(), "Desc_Screw_C".to_string())];
+        assert!((input.rate - 1.0).abs() < 1e-12);
+        assert_eq!(input.exact_rate, Some(exact::to_rational(1.0)));
+        factory.add_node_output("Recipe_Rotor_C", "Desc_Rotor_C", "Desc_Rotor_C", get_item_rate(1.0, None));
+        factory.add_node_output("Recipe_Rotor_C", "Desc_Rotor_C", "Desc_Rotor_C", get_item_rate(2.0, Some(exact::to_rational(2.0))));
+        let output: &ItemRate = &factory.nodes["Recipe_Rotor_C"].outputs[&("Desc_Rotor_C".to_string(), "Desc_Rotor_C".to_string())];
+        assert_eq!(output.rate, 3.0);
+        assert_eq!(output.exact_rate, None);
+    }
+
+    #[test]
+    fn solves_recipe_loops() {
+        let data: GameData = GameData::new("./static/data.json");
+        let mut solver: Solver = Solver::new(data, HashMap::from([("Desc_Rubber_C".to_string(), 60.0), ("Desc_Plastic_C".to_string(), 60.0)]));
+        let loop_ids: Vec<String> = vec!["Recipe_Alternate_Plastic_1_C".to_string(), "Recipe_Alternate_RecycledRubber_C".to_string()];
+        assert!(solver.get_recipe_cycles().iter().any(|cycle| loop_ids.iter().all(|recipe_id| cycle.contains(recipe_id))));
+        let factory: Factory = solver.solve();
+        assert!(factory.cycles.contains(&loop_ids));
+        for recipe_id in loop_ids.iter() {
+            assert!(factory.nodes[recipe_id].in_cycle);
+        }
+        let recycled_plastic: &Node = &factory.nodes["Recipe_Alternate_Plastic_1_C"];
+        assert!(recycled_plastic.outputs.keys().any(|(destination, _)| destination == "Recipe_Alternate_RecycledRubber_C"));
+        assert!(recycled_plastic.inputs.keys().any(|(source, _)| source == "Recipe_Alternate_RecycledRubber_C"));
+        assert!(factory.verify(solver.get_data()).is_empty());
+        assert!(factory.nodes.values().filter(|&node| !loop_ids.contains(&node.id)).all(|node| !node.in_cycle));
+    }
+
+    #[test]
+    fn indexes_every_link() {
+        let data: GameData = GameData::new("./static/data.json");
+        let mut solver: Solver = Solver::new(data, HashMap::from([("Desc_Computer_C".to_string(), 5.0), ("Desc_Plastic_C".to_string(), 60.0)]));
+        solver.set_self_powered(true);
+        solver.build_model();
+        let mut scanned: HashMap<(String, String), Vec<usize>> = HashMap::new();
+        let mut outgoing: HashMap<(String, String), Vec<usize>> = HashMap::new();
+        for (source, links) in solver.links.links.iter() {
+            for link in links.iter() {
+                scanned.entry((link.destination.clone(), link.item.clone())).or_default().push(link.variable.idx());
+                outgoing.entry((source.clone(), link.item.clone())).or_default().push(link.variable.idx());
+            }
+        }
+        for ((destination, item), mut variables) in scanned.into_iter() {
+            let mut indexed: Vec<usize> = solver.links.get_incoming_for_item(&destination, &item).iter().map(|&l| l.variable.idx()).collect::<Vec<usize>>();
+            indexed.sort();
+            variables.sort();
+            assert_eq!(indexed, variables, "incoming {} to {}", item, destination);
+        }
+        for ((source, item), variables) in outgoing.into_iter() {
+            let indexed: Vec<usize> = solver.links.get_outgoing_for_item(&source, &item).iter().map(|&l| l.variable.idx()).collect::<Vec<usize>>();
+            assert_eq!(indexed, variables, "outgoing {} from {}", item, source);
+        }
+    }
+
+    #[test]
+    fn plans_a_remainder_machine() {
+        let setting: ClockSetting = get_level_setting(6.25, 1.0);
+        assert_eq!(setting.full_machines, vec![(6, 1.0)]);
+        assert!((setting.remainder_clock.unwrap() - 0.25).abs() < 1e-9);
+        assert_eq!(setting.get_buildings(), 7);
+        assert_eq!(setting.get_shards(), 0);
+        let setting: ClockSetting = get_level_setting(5.5, 2.5);
+        assert_eq!(setting.full_machines, vec![(2, 2.5)]);
+        assert!((setting.remainder_clock.unwrap() - 0.5).abs() < 1e-9);
+        assert_eq!(setting.get_shards(), 6);
+        assert!((setting.get_average_clock() - 5.5 / 3.0).abs() < 1e-9);
+        let setting: ClockSetting = get_level_setting(7.5, 2.5);
+        assert_eq!(setting.remainder_clock, None);
+        assert_eq!(setting.get_buildings(), 3);
+    }
+
+    #[test]
+    fn plans_the_fewest_shards_for_model_counts() {
+        //Two full machines at 250%, one with the leftover shard and the spare capacity, and one at 100%
+        let setting: ClockSetting = get_shard_setting(7.25, 4, 3);
+        assert_eq!(setting.full_machines, vec![(2, 2.5), (1, 1.0)]);
+        assert!((setting.remainder_clock.unwrap() - 1.25).abs() < 1e-9);
+        assert_eq!(setting.get_shards(), 7);
+        let setting: ClockSetting = get_shard_setting(6.25, 7, 3);
+        assert_eq!(setting.full_machines, vec![(6, 1.0)]);
+        assert!((setting.remainder_clock.unwrap() - 0.25).abs() < 1e-9);
+        let setting: ClockSetting = get_shard_setting(2.5, 4, 3);
+        assert_eq!(setting.full_machines, vec![(4, 0.625)]);
+        assert_eq!(setting.remainder_clock, None);
+        let setting: ClockSetting = get_shard_setting(7.4, 3, 3);
+        assert_eq!(setting.full_machines, vec![(2, 2.5)]);
+        assert!((setting.remainder_clock.unwrap() - 2.4).abs() < 1e-9);
+        assert_eq!(setting.get_shards(), 9);
+    }
+
+    fn solve_rotors(integer_buildings: bool, clock_policy: ClockPolicy) -> Factory {
+        let data: GameData = GameData::new("./static/data.json");
+        let mut solver: Solver = Solver::new(data, HashMap::from([("Desc_Rotor_C".to_string(), 10.0)]));
+        solver.remove_alternates();
+        solver.set_integer_buildings(integer_buildings);
+        solver.set_clock_policy(clock_policy);
+        let factory: Factory = solver.solve();
+        assert!(factory.verify(solver.get_data()).is_empty());
+        for node in factory.nodes.values().filter(|&node| node.buildings > 0.0) {
+            let machines: &MachineCount = node.machines.as_ref().unwrap();
+            let planned: u32 = machines.full_machines.iter().map(|&(count, _)| count).sum::<u32>() + machines.remainder_clock.is_some() as u32;
+            assert_eq!(planned as f64, node.buildings);
+        }
+        return factory;
+    }
+
+    #[test]
+    fn keeps_integer_plans_within_the_shard_budget() {
+        for shard_budget in [0, 3, 10] {
+            let factory: Factory = solve_rotors(true, ClockPolicy::new(2.5, 0.1, shard_budget, ClockPreference::FewerMachines));
+            assert!(factory.nodes.values().map(|node| node.shards).sum::<u32>() <= shard_budget);
+        }
+        let unlimited: Factory = solve_rotors(true, ClockPolicy::new(2.5, 0.1, 100, ClockPreference::FewerMachines));
+        let limited: Factory = solve_rotors(true, ClockPolicy::new(2.5, 0.1, 3, ClockPreference::FewerMachines));
+        assert!(limited.summary().machines.values().sum::<f64>() > unlimited.summary().machines.values().sum::<f64>());
+    }
+
+    #[test]
+    fn lowers_power_without_shards() {
+        for integer_buildings in [false, true] {
+            let factory: Factory = solve_rotors(integer_buildings, ClockPolicy::new(2.5, 0.01, 10, ClockPreference::LowerPower));
+            for node in factory.nodes.values().filter(|&node| node.buildings > 0.0) {
+                assert_eq!(node.shards, 0);
+                assert!(node.clock <= 1.0 + CLOCK_TOLERANCE);
+                //Extra machines stop paying for themselves long before 1% clock
+                assert!(node.clock > 0.5, "{} runs at {}", node.name, node.clock);
+            }
+        }
+    }
+}